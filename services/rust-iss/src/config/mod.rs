@@ -3,11 +3,16 @@ use r2d2_redis::RedisConnectionManager;
 use sqlx::PgPool;
 use std::sync::Arc;
 
-use crate::domain::models::AppState;
-use crate::repo::{cache_repo::CacheRepo, iss_repo::IssRepo, osdr_repo::OsdrRepo};
+use crate::domain::models::{AppState, ISS_NORAD_ID};
+use crate::domain::quality::SpeedCheck;
+use crate::repo::{
+    cache_repo::CacheRepo, geofence_repo::GeofenceRepo, iss_repo::IssRepo, osdr_repo::OsdrRepo,
+    space_repo::SpaceRepo, tle_repo::TleRepo,
+};
 use crate::services::{
-    iss_service::IssService, job_service::JobService, osdr_service::OsdrService,
-    space_service::SpaceService,
+    geofence_service::GeofenceService, iss_service::IssService, job_service::JobService,
+    orbit_service::OrbitService, osdr_service::OsdrService, position_provider::build_providers,
+    retention_service::RetentionService, space_service::SpaceService,
 };
 
 pub async fn new(pool: PgPool) -> AppState {
@@ -24,6 +29,9 @@ pub async fn new(pool: PgPool) -> AppState {
     let iss_repo = IssRepo::new(pool.clone());
     let osdr_repo = OsdrRepo::new(pool.clone());
     let cache_repo = CacheRepo::new(redis_pool);
+    let tle_repo = TleRepo::new(pool.clone());
+    let geofence_repo = GeofenceRepo::new(pool.clone());
+    let space_repo = SpaceRepo::new(pool.clone());
 
     // Config variables
     let nasa_url = env_str("NASA_API_URL", "https://visualization.osdr.nasa.gov/biodata/api/v2/datasets/?format=json");
@@ -33,7 +41,13 @@ pub async fn new(pool: PgPool) -> AppState {
     let donki_flr_url = env_str("DONKI_FLR_API_URL", "https://api.nasa.gov/DONKI/FLR");
     let donki_cme_url = env_str("DONKI_CME_API_URL", "https://api.nasa.gov/DONKI/CME");
     let iss_url = env_str("ISS_API_URL", "https://api.wheretheiss.at/v1/satellites/25544");
+    let satellite_url_template = env_str("SATELLITE_API_URL", "https://api.wheretheiss.at/v1/satellites/{id}");
+    let tracked_satellites = env_ids("TRACKED_SATELLITES", &[ISS_NORAD_ID]);
+    let open_notify_url = env_str("OPEN_NOTIFY_API_URL", "http://api.open-notify.org/iss-now.json");
+    let iss_providers = env_str("ISS_PROVIDERS", "wheretheiss,open-notify,tle");
     let spacex_next_url = env_str("SPACEX_NEXT_API_URL", "https://api.spacexdata.com/v4/launches/next");
+    let tle_url = env_str("TLE_API_URL", "https://celestrak.org/NORAD/elements/gp.php?CATNR=25544&FORMAT=TLE");
+    let tle_file = env_str("TLE_FILE_PATH", "/app/data/iss.tle"); // не входит в образ, монтируется
     let jwst_api_url = env_str("JWST_API_URL", "");
     let jwst_api_key = env_str("JWST_API_KEY", "");
     let astro_api_url = env_str("ASTRONOMY_API_URL", "https://api.astronomyapi.com/api/v2");
@@ -46,13 +60,50 @@ pub async fn new(pool: PgPool) -> AppState {
     let every_neo = env_u64("NEO_EVERY_SECONDS", 7200); // 2ч
     let every_donki = env_u64("DONKI_EVERY_SECONDS", 3600); // 1ч
     let every_spacex = env_u64("SPACEX_EVERY_SECONDS", 3600);
+    let every_tle = env_u64("TLE_EVERY_SECONDS", 21600); // 6ч
+    let every_retention = env_u64("RETENTION_EVERY_SECONDS", 3600); // 1ч
+    let every_partition = env_u64("PARTITION_EVERY_SECONDS", 86400); // 24ч
+    let retention_days = env_u64("ISS_RETENTION_DAYS", 30) as i64;
+    let rollup_minutes = env_u64("ISS_ROLLUP_MINUTES", 10) as i64;
     let rate_limit_seconds = env_u64("RATE_LIMIT_SECONDS", 1);
+    let iss_trend_samples = env_u64("ISS_TREND_SAMPLES", 10) as i64;
+    let osdr_list_limit = env_u64("OSDR_LIST_LIMIT", 20) as i64;
+    let osdr_withdraw_after = env_u64("OSDR_WITHDRAW_AFTER_SYNCS", 3) as i32;
+    let osdr_mapping_path = env_str("OSDR_MAPPING_PATH", "/app/data/osdr_mapping.json");
+    let speed_check = SpeedCheck {
+        expected_kmh: env_f64("ISS_EXPECTED_SPEED_KMH", 27600.0),
+        tolerance: env_f64("ISS_SPEED_TOLERANCE", 0.25),
+    };
 
     // Services
-    let iss_service = IssService::new(iss_repo.clone(), iss_url.clone());
-    let osdr_service = OsdrService::new(osdr_repo.clone(), nasa_url.clone());
+    let geofence_service = GeofenceService::new(geofence_repo.clone());
+    let orbit_service = OrbitService::new(tle_repo.clone(), tle_url.clone(), tle_file.clone());
+    let providers = build_providers(
+        &iss_providers,
+        &iss_url,
+        &satellite_url_template,
+        &open_notify_url,
+        &orbit_service,
+    );
+    let iss_service = IssService::new(
+        iss_repo.clone(),
+        providers,
+        tracked_satellites.clone(),
+        iss_trend_samples,
+        speed_check,
+        geofence_service.clone(),
+    );
+    let osdr_service = OsdrService::new(
+        osdr_repo.clone(),
+        cache_repo.clone(),
+        nasa_url.clone(),
+        osdr_withdraw_after,
+        osdr_mapping_path,
+    );
+    let retention_service = RetentionService::new(iss_repo.clone(), retention_days, rollup_minutes);
     let space_service = SpaceService::new(
         cache_repo.clone(),
+        space_repo.clone(),
         nasa_key.clone(),
         apod_url.clone(),
         neo_url.clone(),
@@ -65,12 +116,17 @@ pub async fn new(pool: PgPool) -> AppState {
         Arc::new(iss_service.clone()),
         Arc::new(osdr_service.clone()),
         Arc::new(space_service.clone()),
+        Arc::new(orbit_service.clone()),
+        Arc::new(retention_service.clone()),
         every_iss,
         every_osdr,
         every_apod,
         every_neo,
         every_donki,
         every_spacex,
+        every_tle,
+        every_retention,
+        every_partition,
     );
 
     AppState {
@@ -78,18 +134,30 @@ pub async fn new(pool: PgPool) -> AppState {
         iss_repo,
         osdr_repo,
         cache_repo,
+        tle_repo,
+        geofence_repo,
+        space_repo,
         iss_service,
         osdr_service,
         space_service,
+        orbit_service,
+        geofence_service,
+        retention_service,
         job_service,
         nasa_url,
         nasa_key,
         iss_url,
+        satellite_url_template,
+        open_notify_url,
+        iss_providers,
+        tracked_satellites,
         apod_url,
         neo_url,
         donki_flr_url,
         donki_cme_url,
         spacex_next_url,
+        tle_url,
+        tle_file,
         jwst_api_url,
         jwst_api_key,
         astro_api_url,
@@ -101,6 +169,12 @@ pub async fn new(pool: PgPool) -> AppState {
         every_neo,
         every_donki,
         every_spacex,
+        every_tle,
+        every_retention,
+        every_partition,
+        retention_days,
+        rollup_minutes,
+        osdr_list_limit,
         rate_limit_seconds,
     }
 }
@@ -109,6 +183,19 @@ fn env_u64(k: &str, d: u64) -> u64 {
     std::env::var(k).ok().and_then(|s| s.parse().ok()).unwrap_or(d)
 }
 
+fn env_f64(k: &str, d: f64) -> f64 {
+    std::env::var(k).ok().and_then(|s| s.parse().ok()).unwrap_or(d)
+}
+
 fn env_str(k: &str, d: &str) -> String {
     std::env::var(k).unwrap_or_else(|_| d.to_string())
 }
+
+/// Parses a comma-separated list of NORAD IDs, e.g. `25544,48274`.
+fn env_ids(k: &str, d: &[i64]) -> Vec<i64> {
+    let ids: Vec<i64> = std::env::var(k)
+        .ok()
+        .map(|s| s.split(',').filter_map(|x| x.trim().parse().ok()).collect())
+        .unwrap_or_default();
+    if ids.is_empty() { d.to_vec() } else { ids }
+}
@@ -17,6 +17,11 @@ pub enum ApiError {
         message: String,
         trace_id: String,
     },
+    BadRequest {
+        code: String,
+        message: String,
+        trace_id: String,
+    },
 }
 
 #[derive(Serialize)]
@@ -41,6 +46,9 @@ impl IntoResponse for ApiError {
             ApiError::NotFound { code, message, trace_id } => {
                 (StatusCode::NOT_FOUND, code, message, trace_id)
             }
+            ApiError::BadRequest { code, message, trace_id } => {
+                (StatusCode::BAD_REQUEST, code, message, trace_id)
+            }
         };
 
         let error_body = ErrorBody {
@@ -76,6 +84,14 @@ impl ApiError {
             trace_id: Uuid::new_v4().to_string(),
         }
     }
+
+    pub fn new_bad_request(message: String) -> Self {
+        ApiError::BadRequest {
+            code: "BAD_REQUEST".to_string(),
+            message,
+            trace_id: Uuid::new_v4().to_string(),
+        }
+    }
 }
 
 // Implement From traits for easy error conversion
@@ -1,4 +1,11 @@
 pub mod models;
 pub mod error;
 pub mod validation;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod orbit;
+pub mod solar;
+pub mod passes;
+pub mod track;
+pub mod quality;
+pub mod osdr;
+pub mod osdr_mapping;
\ No newline at end of file
@@ -1,11 +1,18 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_valid::Validate;
 use serde_json::Value;
 use sqlx::{FromRow, PgPool};
 
-use crate::repo::{cache_repo::CacheRepo, iss_repo::IssRepo, osdr_repo::OsdrRepo};
+use crate::domain::solar::Illumination;
+use crate::domain::validation::ValidationIssue;
+use crate::repo::{
+    cache_repo::CacheRepo, geofence_repo::GeofenceRepo, iss_repo::IssRepo, osdr_repo::OsdrRepo,
+    space_repo::SpaceRepo, tle_repo::TleRepo,
+};
 use crate::services::{
-    iss_service::IssService, job_service::JobService, osdr_service::OsdrService,
+    geofence_service::GeofenceService, iss_service::IssService, job_service::JobService,
+    orbit_service::OrbitService, osdr_service::OsdrService, retention_service::RetentionService,
     space_service::SpaceService,
 };
 
@@ -15,20 +22,32 @@ pub struct AppState {
     pub iss_repo: IssRepo,
     pub osdr_repo: OsdrRepo,
     pub cache_repo: CacheRepo,
+    pub tle_repo: TleRepo,
+    pub geofence_repo: GeofenceRepo,
+    pub space_repo: SpaceRepo,
 
     pub iss_service: IssService,
     pub osdr_service: OsdrService,
     pub space_service: SpaceService,
+    pub orbit_service: OrbitService,
+    pub geofence_service: GeofenceService,
+    pub retention_service: RetentionService,
     pub job_service: JobService,
     
     pub nasa_url: String,
     pub nasa_key: String,
     pub iss_url: String,
+    pub satellite_url_template: String,
+    pub open_notify_url: String,
+    pub iss_providers: String,
+    pub tracked_satellites: Vec<i64>,
     pub apod_url: String,
     pub neo_url: String,
     pub donki_flr_url: String,
     pub donki_cme_url: String,
     pub spacex_next_url: String,
+    pub tle_url: String,
+    pub tle_file: String,
 
     pub jwst_api_url: String,
     pub jwst_api_key: String,
@@ -42,22 +61,274 @@ pub struct AppState {
     pub every_neo: u64,
     pub every_donki: u64,
     pub every_spacex: u64,
+    pub every_tle: u64,
+    pub every_retention: u64,
+    pub every_partition: u64,
+    pub retention_days: i64,
+    pub rollup_minutes: i64,
+    /// Default page size of `GET /osdr/list`.
+    pub osdr_list_limit: i64,
     pub rate_limit_seconds: u64,
 }
 
+/// NORAD catalog number of the ISS.
+pub const ISS_NORAD_ID: i64 = 25544;
+
 #[derive(Serialize)]
 pub struct Health { pub status: &'static str, pub now: DateTime<Utc> }
 
-#[derive(Serialize, FromRow, Debug)]
+#[derive(Serialize, FromRow, Debug, Clone)]
 pub struct IssFetchLog {
     pub id: i64,
+    pub satellite_id: i64,
     pub fetched_at: DateTime<Utc>,
     pub source_url: String,
     pub payload: Value,
+    /// `ok`, or the anomaly detected at ingest (see `domain::quality`).
+    pub quality: String,
+    /// Typed position columns, `NULL` when the payload failed validation.
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub altitude: Option<f64>,
+    pub velocity: Option<f64>,
+    pub visibility: Option<String>,
+    pub footprint: Option<f64>,
+}
+
+/// Satellite position parsed and validated from the upstream payload at ingest.
+#[derive(Deserialize, Validate, Debug, Clone)]
+pub struct IssPosition {
+    #[validate(minimum = -90.0)]
+    #[validate(maximum = 90.0)]
+    pub latitude: f64,
+    #[validate(minimum = -180.0)]
+    #[validate(maximum = 180.0)]
+    pub longitude: f64,
+    /// Kilometers above the ellipsoid; not every provider reports it.
+    #[validate(exclusive_minimum = 0.0)]
+    pub altitude: Option<f64>,
+    /// Kilometers per hour; not every provider reports it.
+    #[validate(minimum = 0.0)]
+    pub velocity: Option<f64>,
+    /// `daylight`, `eclipsed` or `visible`, as reported upstream.
+    pub visibility: Option<String>,
+    /// Diameter of the visibility footprint in kilometers.
+    pub footprint: Option<f64>,
 }
 
+impl IssPosition {
+    pub fn parse(payload: &Value) -> Result<Self, String> {
+        let position: Self = serde_json::from_value(payload.clone()).map_err(|e| e.to_string())?;
+        position.validate().map_err(|e| e.to_string())?;
+        Ok(position)
+    }
+}
 
-#[derive(Serialize)]
+/// Stable v2 response shape for a stored satellite position.
+#[derive(Serialize, Debug)]
+pub struct IssPositionResponse {
+    pub satellite_id: i64,
+    pub fetched_at: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude_km: Option<f64>,
+    pub velocity_kmh: Option<f64>,
+    pub visibility: Option<String>,
+    pub footprint_km: Option<f64>,
+    pub quality: String,
+}
+
+impl IssPositionResponse {
+    /// Returns `None` for rows without typed coordinates.
+    pub fn from_log(log: &IssFetchLog) -> Option<Self> {
+        Some(Self {
+            satellite_id: log.satellite_id,
+            fetched_at: log.fetched_at,
+            latitude: log.lat?,
+            longitude: log.lon?,
+            altitude_km: log.altitude,
+            velocity_kmh: log.velocity,
+            visibility: log.visibility.clone(),
+            footprint_km: log.footprint,
+            quality: log.quality.clone(),
+        })
+    }
+}
+
+/// A stored two-line element set.
+#[derive(Serialize, FromRow, Debug, Clone)]
+pub struct TleSet {
+    pub id: i64,
+    pub norad_id: i64,
+    pub name: Option<String>,
+    pub line1: String,
+    pub line2: String,
+    pub epoch: DateTime<Utc>,
+    pub source: String,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Query parameters for `GET /iss/predict`.
+#[derive(Deserialize, Debug, Default)]
+pub struct IssPredictQuery {
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Query parameters for `GET /iss/passes`. `alt` is the observer altitude in meters.
+#[derive(Deserialize, Debug)]
+pub struct IssPassesQuery {
+    pub lat: f64,
+    pub lon: f64,
+    pub alt: Option<f64>,
+    pub days: Option<i64>,
+    pub min_elevation: Option<f64>,
+}
+
+/// A point of an ISS pass as seen by the observer.
+#[derive(Serialize, Debug, Clone)]
+pub struct PassEvent {
+    pub at: DateTime<Utc>,
+    pub azimuth: f64,
+    pub elevation: f64,
+}
+
+/// A single ISS pass over an observer, from rise to set above the horizon.
+#[derive(Serialize, Debug, Clone)]
+pub struct IssPass {
+    pub rise: PassEvent,
+    pub culmination: PassEvent,
+    pub set: PassEvent,
+    pub max_elevation: f64,
+    pub duration_sec: i64,
+    /// The station is sunlit at culmination.
+    pub sunlit: bool,
+    /// The sun is below civil twilight (-6°) for the observer at culmination.
+    pub observer_dark: bool,
+    /// At some point of the pass the station is sunlit while the observer is in darkness.
+    pub visible: bool,
+}
+
+/// A single ISS position extracted from the JSONB payload of a fetch log row.
+#[derive(Serialize, FromRow, Debug, Clone)]
+pub struct IssHistoryPoint {
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude: Option<f64>,
+    pub velocity: Option<f64>,
+    pub fetched_at: DateTime<Utc>,
+    pub quality: String,
+    /// Computed on read, never stored.
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub illumination: Option<Illumination>,
+}
+
+/// Outcome of one pass of the `iss_fetch_log` retention job.
+#[derive(Serialize, FromRow, Debug, Clone)]
+pub struct RetentionRun {
+    pub id: i64,
+    pub ran_at: DateTime<Utc>,
+    /// Raw rows older than this were pruned.
+    pub cutoff: DateTime<Utc>,
+    /// Rollup points written.
+    pub rolled_up: i64,
+    /// Raw rows deleted.
+    pub deleted: i64,
+}
+
+/// One stored fetch of a space API, from `space_cache`.
+#[derive(Serialize, FromRow, Debug, Clone)]
+pub struct SpaceCacheEntry {
+    pub id: i64,
+    pub source: String,
+    pub fetched_at: DateTime<Utc>,
+    pub payload: Value,
+}
+
+/// Query parameters for `GET /space/:src/history`.
+#[derive(Deserialize, Debug, Default)]
+pub struct SpaceHistoryQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+/// Query parameters for `GET /iss/history`. Flagged samples are excluded unless
+/// `exclude_flagged=false`.
+#[derive(Deserialize, Debug, Default)]
+pub struct IssHistoryQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub exclude_flagged: Option<bool>,
+}
+
+/// Geometry of a geofence. Polygon vertices are `[lon, lat]` pairs, as in GeoJSON,
+/// and polygons must not cross the antimeridian.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GeofenceShape {
+    Circle { lat: f64, lon: f64, radius_km: f64 },
+    Polygon { coordinates: Vec<[f64; 2]> },
+}
+
+/// A named region whose enter/exit transitions are recorded as events.
+#[derive(Serialize, FromRow, Debug, Clone)]
+pub struct Geofence {
+    pub id: i64,
+    pub name: String,
+    pub shape: sqlx::types::Json<GeofenceShape>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request body for `POST /geofences`.
+#[derive(Deserialize, Debug)]
+pub struct NewGeofence {
+    pub name: String,
+    pub shape: GeofenceShape,
+}
+
+/// A satellite entering or leaving a geofence.
+#[derive(Serialize, FromRow, Debug, Clone)]
+pub struct GeofenceEvent {
+    pub id: i64,
+    pub geofence_id: i64,
+    pub geofence_name: String,
+    pub satellite_id: i64,
+    pub event: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Query parameters for `GET /iss/geofence-events`.
+#[derive(Deserialize, Debug, Default)]
+pub struct GeofenceEventsQuery {
+    pub geofence_id: Option<i64>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+/// Query parameters for `GET /space/terminator`. `step` is the longitude resolution in degrees.
+#[derive(Deserialize, Debug, Default)]
+pub struct TerminatorQuery {
+    pub at: Option<DateTime<Utc>>,
+    pub step: Option<f64>,
+}
+
+/// Query parameters for `GET /iss/trend`. `minutes` takes precedence over `samples`.
+/// Flagged samples are excluded unless `exclude_flagged=false`.
+#[derive(Deserialize, Debug, Default)]
+pub struct IssTrendQuery {
+    pub samples: Option<i64>,
+    pub minutes: Option<i64>,
+    pub exclude_flagged: Option<bool>,
+}
+
+/// ISS movement over a window of samples. The first ten fields keep their
+/// original two-point meaning, now measured between the window's first and last sample.
+#[derive(Serialize, Default)]
 pub struct Trend {
     pub movement: bool,
     pub delta_km: f64,
@@ -69,4 +340,209 @@ pub struct Trend {
     pub from_lon: Option<f64>,
     pub to_lat: Option<f64>,
     pub to_lon: Option<f64>,
-}
\ No newline at end of file
+
+    pub samples: usize,
+    pub total_distance_km: f64,
+    pub mean_velocity_kmh: Option<f64>,
+    pub min_velocity_kmh: Option<f64>,
+    pub max_velocity_kmh: Option<f64>,
+    pub altitude_drift_km: Option<f64>,
+    pub gaps: usize,
+    pub max_gap_sec: Option<f64>,
+}
+
+/// An OSDR dataset as extracted from an upstream page, ready to be stored.
+#[derive(Debug, Clone)]
+pub struct NewOsdrItem {
+    pub dataset_id: String,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub raw: Value,
+}
+
+/// Counters of one OSDR sync.
+#[derive(Serialize, Debug, Default, Clone)]
+pub struct OsdrSyncSummary {
+    pub pages: usize,
+    /// Items read from upstream.
+    pub fetched: usize,
+    /// Items rejected by validation or without a dataset ID.
+    pub invalid: usize,
+    /// Items not updated since the previous watermark.
+    pub skipped: usize,
+    /// Items inserted or changed.
+    pub written: usize,
+    /// Items whose content hash matched the stored one.
+    pub unchanged: usize,
+    /// Items newly marked as withdrawn because they vanished from upstream.
+    pub withdrawn: usize,
+    /// Newest upstream `updated_at` seen so far.
+    pub watermark: Option<DateTime<Utc>>,
+}
+
+/// One recorded OSDR sync, from `osdr_sync_runs`.
+#[derive(Serialize, FromRow, Debug, Clone)]
+pub struct OsdrSyncRun {
+    pub id: i64,
+    /// `true` if the watermark was ignored.
+    pub full_sync: bool,
+    /// `running`, `succeeded`, `failed` or `interrupted`.
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub pages: i32,
+    pub fetched: i32,
+    pub upserted: i32,
+    pub unchanged: i32,
+    pub skipped: i32,
+    pub invalid: i32,
+    pub withdrawn: i32,
+    pub error: Option<String>,
+}
+
+/// An upstream OSDR item that failed validation, to be quarantined.
+#[derive(Debug, Clone)]
+pub struct NewRejectedOsdrItem {
+    /// Dataset ID found by the mapping rules, if any.
+    pub dataset_id: Option<String>,
+    pub raw: Value,
+    pub errors: Vec<ValidationIssue>,
+}
+
+/// A quarantined OSDR item from `osdr_rejected_items`.
+#[derive(Serialize, FromRow, Debug, Clone)]
+pub struct OsdrRejectedItem {
+    pub id: i64,
+    pub source: String,
+    pub dataset_id: Option<String>,
+    pub raw: Value,
+    /// `[{ path, message }]` from the last validation.
+    pub errors: Value,
+    /// Sync run that last rejected the item.
+    pub run_id: Option<i64>,
+    pub first_rejected_at: DateTime<Utc>,
+    pub last_rejected_at: DateTime<Utc>,
+    pub times_rejected: i32,
+}
+
+/// Outcome of re-validating the quarantined OSDR items.
+#[derive(Serialize, Debug, Default, Clone)]
+pub struct OsdrRevalidation {
+    pub checked: usize,
+    /// Items that now pass and were moved into `osdr_items`.
+    pub accepted: usize,
+    pub still_rejected: usize,
+}
+
+/// One stored content version of an OSDR dataset.
+#[derive(Serialize, FromRow, Debug, Clone)]
+pub struct OsdrItemVersion {
+    pub dataset_id: String,
+    pub version: i32,
+    /// MD5 of the raw record's canonical JSONB text.
+    pub content_hash: String,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub raw: Value,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Query parameters for `GET /osdr/:dataset_id/history`. With both `from` and `to`,
+/// the response also carries the diff between those two versions.
+#[derive(Deserialize, Debug, Default)]
+pub struct OsdrHistoryQuery {
+    pub from: Option<i32>,
+    pub to: Option<i32>,
+}
+
+/// Query parameters for `GET /osdr/sync`.
+#[derive(Deserialize, Debug, Default)]
+pub struct OsdrSyncQuery {
+    pub full: Option<bool>,
+}
+
+/// Query parameters for `GET /osdr/list`. `sort` is a column name, prefixed with `-` for descending order.
+#[derive(Deserialize, Debug, Default)]
+pub struct OsdrListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub status: Option<String>,
+    pub updated_since: Option<DateTime<Utc>>,
+    pub q: Option<String>,
+    pub sort: Option<String>,
+    /// `exclude` (default), `only` or `include` datasets withdrawn upstream.
+    pub withdrawn: Option<String>,
+}
+
+/// Query parameters for `GET /osdr/rejected`.
+#[derive(Deserialize, Debug, Default)]
+pub struct OsdrRejectedQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// Query parameters for `GET /osdr/search`.
+#[derive(Deserialize, Debug, Default)]
+pub struct OsdrSearchQuery {
+    pub q: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// Filters shared by the OSDR listing queries.
+#[derive(Debug, Default, Clone)]
+pub struct OsdrFilter {
+    /// Exact status, compared case-insensitively.
+    pub status: Option<String>,
+    pub updated_since: Option<DateTime<Utc>>,
+    /// Substring of the title or dataset ID.
+    pub q: Option<String>,
+    /// `Some(true)` keeps only withdrawn items, `Some(false)` hides them.
+    pub withdrawn: Option<bool>,
+}
+
+/// Sort orders accepted by `GET /osdr/list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OsdrSort {
+    #[default]
+    InsertedDesc,
+    InsertedAsc,
+    UpdatedDesc,
+    UpdatedAsc,
+    TitleAsc,
+    TitleDesc,
+    DatasetIdAsc,
+    DatasetIdDesc,
+}
+
+impl OsdrSort {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "-inserted_at" => Some(Self::InsertedDesc),
+            "inserted_at" => Some(Self::InsertedAsc),
+            "-updated_at" => Some(Self::UpdatedDesc),
+            "updated_at" => Some(Self::UpdatedAsc),
+            "title" => Some(Self::TitleAsc),
+            "-title" => Some(Self::TitleDesc),
+            "dataset_id" => Some(Self::DatasetIdAsc),
+            "-dataset_id" => Some(Self::DatasetIdDesc),
+            _ => None,
+        }
+    }
+
+    /// `ORDER BY` clause, with `id` as a tie-breaker so pages never overlap.
+    pub fn order_by(&self) -> &'static str {
+        match self {
+            Self::InsertedDesc => "inserted_at DESC, id DESC",
+            Self::InsertedAsc => "inserted_at ASC, id ASC",
+            Self::UpdatedDesc => "updated_at DESC NULLS LAST, id DESC",
+            Self::UpdatedAsc => "updated_at ASC NULLS LAST, id ASC",
+            Self::TitleAsc => "title ASC NULLS LAST, id ASC",
+            Self::TitleDesc => "title DESC NULLS LAST, id DESC",
+            Self::DatasetIdAsc => "dataset_id ASC NULLS LAST, id ASC",
+            Self::DatasetIdDesc => "dataset_id DESC NULLS LAST, id DESC",
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// WGS84 equatorial radius in kilometers.
+pub const WGS84_A: f64 = 6378.137;
+/// WGS84 flattening.
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+/// Geodetic state of a satellite computed by the propagator.
+#[derive(Serialize, Debug, Clone)]
+pub struct OrbitState {
+    pub at: DateTime<Utc>,
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude_km: f64,
+    pub velocity_kmh: f64,
+    /// Earth-fixed position in kilometers, used for look-angle and shadow calculations.
+    #[serde(skip)]
+    pub ecef_km: [f64; 3],
+}
+
+/// Direction and distance from a ground observer to a target.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct LookAngles {
+    pub azimuth: f64,
+    pub elevation: f64,
+    pub range_km: f64,
+}
+
+/// SGP4 propagator built from a single two-line element set.
+#[derive(Clone)]
+pub struct Propagator {
+    elements: sgp4::Elements,
+    constants: sgp4::Constants,
+}
+
+impl Propagator {
+    /// Parses a TLE and prepares the SGP4 constants for it.
+    pub fn from_tle(name: Option<&str>, line1: &str, line2: &str) -> Result<Self> {
+        let elements = sgp4::Elements::from_tle(
+            name.map(str::to_string),
+            line1.trim().as_bytes(),
+            line2.trim().as_bytes(),
+        )
+        .map_err(|e| anyhow!("Invalid TLE: {}", e))?;
+        let constants = sgp4::Constants::from_elements(&elements)
+            .map_err(|e| anyhow!("Unusable TLE elements: {}", e))?;
+        Ok(Self { elements, constants })
+    }
+
+    pub fn norad_id(&self) -> u64 {
+        self.elements.norad_id
+    }
+
+    pub fn epoch(&self) -> DateTime<Utc> {
+        self.elements.datetime.and_utc()
+    }
+
+    /// Propagates the orbit to `at` and converts the result to geodetic coordinates.
+    pub fn propagate(&self, at: DateTime<Utc>) -> Result<OrbitState> {
+        let minutes = self
+            .elements
+            .datetime_to_minutes_since_epoch(&at.naive_utc())
+            .map_err(|e| anyhow!("Time out of range: {}", e))?;
+        let prediction = self
+            .constants
+            .propagate(minutes)
+            .map_err(|e| anyhow!("SGP4 propagation failed: {}", e))?;
+
+        let ecef_km = teme_to_ecef(prediction.position, at);
+        let (lat, lon, altitude_km) = ecef_to_geodetic(ecef_km);
+        let v = prediction.velocity;
+        let velocity_kmh = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt() * 3600.0;
+
+        Ok(OrbitState { at, lat, lon, altitude_km, velocity_kmh, ecef_km })
+    }
+}
+
+/// Extracts `(name, line1, line2)` from a TLE or 3LE text block.
+/// Only the first element set in the text is returned.
+pub fn parse_tle_text(text: &str) -> Option<(Option<String>, String, String)> {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).filter(|l| !l.trim().is_empty()).collect();
+    let idx = lines.iter().position(|l| l.starts_with("1 "))?;
+    let line2 = lines.get(idx + 1).filter(|l| l.starts_with("2 "))?;
+    let name = if idx > 0 {
+        Some(lines[idx - 1].trim_start_matches("0 ").trim().to_string())
+    } else {
+        None
+    };
+    Some((name, lines[idx].to_string(), line2.to_string()))
+}
+
+/// Julian date of a UTC instant (UT1 is approximated by UTC).
+pub fn julian_date(t: DateTime<Utc>) -> f64 {
+    t.timestamp_millis() as f64 / 86_400_000.0 + 2_440_587.5
+}
+
+/// Greenwich mean sidereal time in radians (IAU 1982 model).
+pub fn gmst_rad(t: DateTime<Utc>) -> f64 {
+    let tu = (julian_date(t) - 2_451_545.0) / 36_525.0;
+    let seconds = 67_310.548_41 + (876_600.0 * 3600.0 + 8_640_184.812_866) * tu
+        + 0.093_104 * tu * tu
+        - 6.2e-6 * tu * tu * tu;
+    (seconds % 86_400.0 / 240.0).to_radians().rem_euclid(std::f64::consts::TAU)
+}
+
+/// Rotates a TEME position into the Earth-fixed frame (polar motion is ignored).
+pub fn teme_to_ecef(r: [f64; 3], t: DateTime<Utc>) -> [f64; 3] {
+    let (s, c) = gmst_rad(t).sin_cos();
+    [c * r[0] + s * r[1], -s * r[0] + c * r[1], r[2]]
+}
+
+/// Converts an Earth-fixed position in kilometers to geodetic `(lat°, lon°, altitude km)`.
+pub fn ecef_to_geodetic(r: [f64; 3]) -> (f64, f64, f64) {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let p = (r[0] * r[0] + r[1] * r[1]).sqrt();
+    let lon = r[1].atan2(r[0]);
+    let mut lat = r[2].atan2(p * (1.0 - e2));
+    let mut n = WGS84_A;
+    for _ in 0..5 {
+        n = WGS84_A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        lat = (r[2] + n * e2 * lat.sin()).atan2(p);
+    }
+    let altitude = p / lat.cos() - n;
+    (lat.to_degrees(), lon.to_degrees(), altitude)
+}
+
+/// Converts geodetic coordinates (degrees, kilometers) to an Earth-fixed position in kilometers.
+pub fn geodetic_to_ecef(lat: f64, lon: f64, alt_km: f64) -> [f64; 3] {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let (slat, clat) = lat.to_radians().sin_cos();
+    let (slon, clon) = lon.to_radians().sin_cos();
+    let n = WGS84_A / (1.0 - e2 * slat * slat).sqrt();
+    [
+        (n + alt_km) * clat * clon,
+        (n + alt_km) * clat * slon,
+        (n * (1.0 - e2) + alt_km) * slat,
+    ]
+}
+
+/// Azimuth (degrees from north), elevation (degrees) and range of `target_ecef_km`
+/// as seen by an observer at the given geodetic position.
+pub fn look_angles(lat: f64, lon: f64, alt_km: f64, target_ecef_km: [f64; 3]) -> LookAngles {
+    let o = geodetic_to_ecef(lat, lon, alt_km);
+    let d = [target_ecef_km[0] - o[0], target_ecef_km[1] - o[1], target_ecef_km[2] - o[2]];
+    let (slat, clat) = lat.to_radians().sin_cos();
+    let (slon, clon) = lon.to_radians().sin_cos();
+
+    let east = -slon * d[0] + clon * d[1];
+    let north = -slat * clon * d[0] - slat * slon * d[1] + clat * d[2];
+    let up = clat * clon * d[0] + clat * slon * d[1] + slat * d[2];
+
+    LookAngles {
+        azimuth: east.atan2(north).to_degrees().rem_euclid(360.0),
+        elevation: up.atan2((east * east + north * north).sqrt()).to_degrees(),
+        range_km: (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt(),
+    }
+}
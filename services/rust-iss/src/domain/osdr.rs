@@ -0,0 +1,106 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::domain::utils::s_pick;
+
+/// Nested objects deeper than this are not searched for fields.
+const MAX_DEPTH: usize = 6;
+
+/// Fields extracted from an OSDR record, whose `raw` layout differs between API versions
+/// (flat search hits, ISA-Tab style `study.characteristics.organism` keys, nested metadata).
+#[derive(Serialize, Debug, Default, Clone)]
+pub struct OsdrDetails {
+    pub organism: Vec<String>,
+    pub assay_types: Vec<String>,
+    pub mission: Option<String>,
+    pub factors: Vec<String>,
+    /// `{ name, url, size }` per file, as far as the record provides them.
+    pub files: Vec<Value>,
+}
+
+/// Extracts the normalized fields from a raw OSDR record.
+pub fn normalize(raw: &Value) -> OsdrDetails {
+    let mut d = OsdrDetails::default();
+    walk(raw, 0, &mut |key, value| {
+        if key.ends_with("organism") || key == "organisms" {
+            push_strings(&mut d.organism, value);
+        } else if key.ends_with("technologytype") || key.ends_with("measurementtype") || key.starts_with("assaytype") {
+            push_strings(&mut d.assay_types, value);
+        } else if key == "mission" || key.ends_with("missionname") || key == "flightprogram" {
+            if d.mission.is_none() {
+                d.mission = first_string(value);
+            }
+        } else if key.ends_with("factorname") || key == "factors" || key == "factor" {
+            push_strings(&mut d.factors, value);
+        } else if key == "files" || key.ends_with("filelist") || key == "datafiles" || key == "studyfiles" {
+            push_files(&mut d.files, value);
+        } else {
+            return false;
+        }
+        true
+    });
+    d
+}
+
+// --- Private Helper Functions ---
+
+/// Visits every `(normalized key, value)` pair, descending into values the visitor did not consume.
+fn walk(v: &Value, depth: usize, visit: &mut dyn FnMut(&str, &Value) -> bool) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    match v {
+        Value::Object(map) => {
+            for (k, child) in map {
+                if !visit(&normalize_key(k), child) {
+                    walk(child, depth + 1, visit);
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| walk(item, depth + 1, visit)),
+        _ => {}
+    }
+}
+
+/// `Study Assay Technology Type` and `study.assay_technology_type` both become `studyassaytechnologytype`.
+fn normalize_key(k: &str) -> String {
+    k.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+fn first_string(v: &Value) -> Option<String> {
+    let mut out = Vec::new();
+    push_strings(&mut out, v);
+    out.into_iter().next()
+}
+
+/// Appends every distinct non-empty string in `v` (a string, an array, or objects with a name).
+fn push_strings(out: &mut Vec<String>, v: &Value) {
+    match v {
+        Value::String(s) => {
+            let s = s.trim();
+            if !s.is_empty() && !out.iter().any(|x| x == s) {
+                out.push(s.to_string());
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| push_strings(out, item)),
+        Value::Object(_) => {
+            if let Some(name) = s_pick(v, &["name", "value", "term", "label"]) {
+                push_strings(out, &Value::String(name));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn push_files(out: &mut Vec<Value>, v: &Value) {
+    match v {
+        Value::String(name) => out.push(json!({ "name": name, "url": null, "size": null })),
+        Value::Array(items) => items.iter().for_each(|item| push_files(out, item)),
+        Value::Object(_) => out.push(json!({
+            "name": s_pick(v, &["file_name", "name", "filename", "title"]),
+            "url": s_pick(v, &["remote_url", "url", "download_url", "href"]),
+            "size": v.get("file_size").or_else(|| v.get("size")).cloned(),
+        })),
+        _ => {}
+    }
+}
@@ -0,0 +1,182 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Where to look for one normalized field in an upstream OSDR item.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FieldRule {
+    /// Top-level keys, tried in order.
+    pub keys: Vec<String>,
+    /// JSON Pointers such as `/study/identifier`, tried after `keys`.
+    pub pointers: Vec<String>,
+    /// `chrono` formats for string dates, tried after RFC 3339. Only used for timestamps.
+    pub formats: Vec<String>,
+}
+
+/// Field-mapping rules for OSDR items, loaded from `OSDR_MAPPING_PATH`.
+/// Fields missing from the file keep their built-in rules.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct OsdrMapping {
+    pub dataset_id: FieldRule,
+    pub title: FieldRule,
+    pub status: FieldRule,
+    pub updated_at: FieldRule,
+}
+
+impl Default for OsdrMapping {
+    fn default() -> Self {
+        Self {
+            dataset_id: rule(&["dataset_id", "id", "uuid", "studyId", "accession", "osdr_id"], &[]),
+            title: rule(&["title", "name", "label"], &[]),
+            status: rule(&["status", "state", "lifecycle"], &[]),
+            updated_at: rule(
+                &["updated", "updated_at", "modified", "lastUpdated", "timestamp"],
+                &["%Y-%m-%d %H:%M:%S"],
+            ),
+        }
+    }
+}
+
+/// An upstream item's fields after mapping.
+#[derive(Debug, Clone, Default)]
+pub struct MappedItem {
+    pub dataset_id: Option<String>,
+    pub title: Option<String>,
+    pub status: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl OsdrMapping {
+    pub fn map(&self, item: &Value) -> MappedItem {
+        MappedItem {
+            dataset_id: find(item, &self.dataset_id, as_text).map(|(v, _)| v),
+            title: find(item, &self.title, as_text).map(|(v, _)| v),
+            status: find(item, &self.status, as_text).map(|(v, _)| v),
+            updated_at: find(item, &self.updated_at, |v| as_time(v, &self.updated_at.formats)).map(|(v, _)| v),
+        }
+    }
+
+    /// Like `map`, but reports for every field the value and the key or pointer it came from.
+    pub fn explain(&self, item: &Value) -> Value {
+        let text = |r: &FieldRule| match find(item, r, as_text) {
+            Some((value, source)) => json!({ "value": value, "source": source }),
+            None => json!({ "value": null, "source": null }),
+        };
+        let updated_at = match find(item, &self.updated_at, |v| as_time(v, &self.updated_at.formats)) {
+            Some((value, source)) => json!({ "value": value, "source": source }),
+            None => json!({ "value": null, "source": null }),
+        };
+        json!({
+            "dataset_id": text(&self.dataset_id),
+            "title": text(&self.title),
+            "status": text(&self.status),
+            "updated_at": updated_at,
+        })
+    }
+}
+
+// --- Private Helper Functions ---
+
+fn rule(keys: &[&str], formats: &[&str]) -> FieldRule {
+    FieldRule {
+        keys: keys.iter().map(|k| k.to_string()).collect(),
+        pointers: Vec::new(),
+        formats: formats.iter().map(|f| f.to_string()).collect(),
+    }
+}
+
+/// Returns the first value the rule resolves and `parse` accepts, with `key:<k>` or
+/// `pointer:<p>` naming where it was found.
+fn find<T>(item: &Value, rule: &FieldRule, parse: impl Fn(&Value) -> Option<T>) -> Option<(T, String)> {
+    let by_key = rule.keys.iter().map(|k| (item.get(k), format!("key:{}", k)));
+    let by_pointer = rule.pointers.iter().map(|p| (item.pointer(p), format!("pointer:{}", p)));
+    by_key
+        .chain(by_pointer)
+        .find_map(|(v, source)| v.and_then(&parse).map(|parsed| (parsed, source)))
+}
+
+/// Non-empty strings, and numbers as their decimal text.
+fn as_text(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// RFC 3339 or one of `formats` for strings, Unix seconds for integers.
+fn as_time(v: &Value, formats: &[String]) -> Option<DateTime<Utc>> {
+    if let Some(n) = v.as_i64() {
+        return Utc.timestamp_opt(n, 0).single();
+    }
+    let s = v.as_str()?;
+    if let Ok(dt) = s.parse::<DateTime<Utc>>() {
+        return Some(dt);
+    }
+    formats.iter().find_map(|f| {
+        NaiveDateTime::parse_from_str(s, f)
+            .ok()
+            .or_else(|| NaiveDate::parse_from_str(s, f).ok().and_then(|d| d.and_hms_opt(0, 0, 0)))
+            .map(|ndt| Utc.from_utc_datetime(&ndt))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_file_matches_built_in_rules() {
+        let sample: Value = serde_json::from_str(include_str!("../../data/osdr_mapping.json")).unwrap();
+        assert_eq!(sample, serde_json::to_value(OsdrMapping::default()).unwrap());
+    }
+
+    #[test]
+    fn keys_are_tried_in_order() {
+        let mapping = OsdrMapping::default();
+        let item = json!({ "uuid": "u-1", "id": "i-1", "name": "Name", "title": "" });
+        let mapped = mapping.map(&item);
+        assert_eq!(mapped.dataset_id.as_deref(), Some("i-1"));
+        // Empty strings are skipped, so `name` wins over the empty `title`.
+        assert_eq!(mapped.title.as_deref(), Some("Name"));
+        assert_eq!(mapping.explain(&item)["dataset_id"]["source"], "key:id");
+    }
+
+    #[test]
+    fn pointers_are_tried_after_keys() {
+        let mut mapping = OsdrMapping::default();
+        mapping.dataset_id.pointers = vec!["/study/identifier".to_string()];
+        let nested = json!({ "study": { "identifier": "OSD-1" } });
+        assert_eq!(mapping.map(&nested).dataset_id.as_deref(), Some("OSD-1"));
+        assert_eq!(mapping.explain(&nested)["dataset_id"]["source"], "pointer:/study/identifier");
+
+        let both = json!({ "id": 42, "study": { "identifier": "OSD-1" } });
+        assert_eq!(mapping.map(&both).dataset_id.as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn dates_parse_rfc3339_and_configured_formats() {
+        let mut mapping = OsdrMapping::default();
+        let ts = |v: Value| mapping.map(&json!({ "updated": v })).updated_at;
+        let expected = Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap();
+        assert_eq!(ts(json!("2024-05-01T12:30:00Z")), Some(expected));
+        assert_eq!(ts(json!("2024-05-01T15:30:00+03:00")), Some(expected));
+        assert_eq!(ts(json!("2024-05-01 12:30:00")), Some(expected));
+        assert_eq!(ts(json!("01.05.2024")), None);
+
+        mapping.updated_at.formats.push("%d.%m.%Y".to_string());
+        let ts = |v: Value| mapping.map(&json!({ "updated": v })).updated_at;
+        assert_eq!(ts(json!("01.05.2024")), Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).single());
+    }
+
+    #[test]
+    fn integers_are_unix_seconds() {
+        let mapping = OsdrMapping::default();
+        let mapped = mapping.map(&json!({ "timestamp": 1_714_566_600 }));
+        assert_eq!(mapped.updated_at, Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).single());
+        // Non-integer numbers are not timestamps.
+        assert_eq!(mapping.map(&json!({ "timestamp": 1.5 })).updated_at, None);
+    }
+}
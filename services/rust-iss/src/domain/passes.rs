@@ -0,0 +1,213 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::domain::models::{IssPass, PassEvent};
+use crate::domain::orbit::{look_angles, LookAngles, Propagator};
+use crate::domain::solar::{is_sunlit, sun_ecef_km};
+
+/// Coarse search step. ISS passes above the horizon last several minutes, so 30 s cannot skip one.
+const SEARCH_STEP_SEC: i64 = 30;
+/// Sun elevation below which the sky is dark enough to see the station.
+const CIVIL_TWILIGHT_DEG: f64 = -6.0;
+
+/// Ground observer location (degrees, kilometers).
+#[derive(Debug, Clone, Copy)]
+pub struct Observer {
+    pub lat: f64,
+    pub lon: f64,
+    pub alt_km: f64,
+}
+
+impl Observer {
+    fn look_at(&self, propagator: &Propagator, t: DateTime<Utc>) -> Result<LookAngles> {
+        let state = propagator.propagate(t)?;
+        Ok(look_angles(self.lat, self.lon, self.alt_km, state.ecef_km))
+    }
+
+    fn is_dark(&self, t: DateTime<Utc>) -> bool {
+        look_angles(self.lat, self.lon, self.alt_km, sun_ecef_km(t)).elevation < CIVIL_TWILIGHT_DEG
+    }
+
+    /// The station is sunlit while the observer is in darkness.
+    fn can_see(&self, propagator: &Propagator, t: DateTime<Utc>) -> Result<bool> {
+        if !self.is_dark(t) {
+            return Ok(false);
+        }
+        let state = propagator.propagate(t)?;
+        Ok(is_sunlit(state.ecef_km, t))
+    }
+}
+
+/// Finds all passes above the horizon between `from` and `to` whose maximum elevation
+/// reaches `min_elevation`. A pass already in progress at `from` starts at `from`;
+/// a pass still in progress at `to` is dropped.
+pub fn find_passes(
+    propagator: &Propagator,
+    observer: Observer,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    min_elevation: f64,
+) -> Result<Vec<IssPass>> {
+    let step = Duration::seconds(SEARCH_STEP_SEC);
+    let mut passes = Vec::new();
+
+    let mut t = from;
+    let mut prev_el = observer.look_at(propagator, t)?.elevation;
+    let mut rise: Option<DateTime<Utc>> = (prev_el >= 0.0).then_some(from);
+
+    while t < to {
+        let next = t + step;
+        let el = observer.look_at(propagator, next)?.elevation;
+
+        if prev_el < 0.0 && el >= 0.0 {
+            rise = Some(refine_crossing(propagator, &observer, t, next)?);
+        } else if prev_el >= 0.0 && el < 0.0 {
+            if let Some(rise_at) = rise.take() {
+                let set_at = refine_crossing(propagator, &observer, t, next)?;
+                let pass = build_pass(propagator, &observer, rise_at, set_at)?;
+                if pass.max_elevation >= min_elevation {
+                    passes.push(pass);
+                }
+            }
+        }
+
+        prev_el = el;
+        t = next;
+    }
+
+    Ok(passes)
+}
+
+/// Bisects the horizon crossing between `a` and `b` to one-second precision.
+fn refine_crossing(
+    propagator: &Propagator,
+    observer: &Observer,
+    mut a: DateTime<Utc>,
+    mut b: DateTime<Utc>,
+) -> Result<DateTime<Utc>> {
+    let a_above = observer.look_at(propagator, a)?.elevation >= 0.0;
+    while (b - a).num_milliseconds() > 1000 {
+        let mid = a + (b - a) / 2;
+        if (observer.look_at(propagator, mid)?.elevation >= 0.0) == a_above {
+            a = mid;
+        } else {
+            b = mid;
+        }
+    }
+    Ok(b)
+}
+
+fn build_pass(
+    propagator: &Propagator,
+    observer: &Observer,
+    rise_at: DateTime<Utc>,
+    set_at: DateTime<Utc>,
+) -> Result<IssPass> {
+    // Elevation is unimodal over a pass, so a ternary search finds the culmination.
+    let (mut a, mut b) = (rise_at, set_at);
+    while (b - a).num_milliseconds() > 1000 {
+        let m1 = a + (b - a) / 3;
+        let m2 = b - (b - a) / 3;
+        if observer.look_at(propagator, m1)?.elevation < observer.look_at(propagator, m2)?.elevation {
+            a = m1;
+        } else {
+            b = m2;
+        }
+    }
+    let culm_at = a + (b - a) / 2;
+
+    let event = |at: DateTime<Utc>| -> Result<PassEvent> {
+        let look = observer.look_at(propagator, at)?;
+        Ok(PassEvent { at, azimuth: look.azimuth, elevation: look.elevation })
+    };
+    let rise = event(rise_at)?;
+    let culmination = event(culm_at)?;
+    let set = event(set_at)?;
+
+    let culm_state = propagator.propagate(culm_at)?;
+    let sunlit = is_sunlit(culm_state.ecef_km, culm_at);
+    let observer_dark = observer.is_dark(culm_at);
+
+    let mut visible = false;
+    let mut t = rise_at;
+    while t <= set_at && !visible {
+        visible = observer.can_see(propagator, t)?;
+        t += Duration::seconds(SEARCH_STEP_SEC / 2);
+    }
+
+    Ok(IssPass {
+        max_elevation: culmination.elevation,
+        duration_sec: (set_at - rise_at).num_seconds(),
+        rise,
+        culmination,
+        set,
+        sunlit,
+        observer_dark,
+        visible,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::orbit::WGS84_A;
+
+    /// The ISS element set from the Wikipedia TLE article, epoch 2008-09-20 12:25:40 UTC.
+    fn iss_2008() -> Propagator {
+        Propagator::from_tle(
+            Some("ISS (ZARYA)"),
+            "1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn overhead_pass_matches_orbit_geometry() {
+        let propagator = iss_2008();
+        // Stand right below the station an hour after epoch: it must culminate at the zenith then.
+        let t0 = propagator.epoch() + Duration::hours(1);
+        let state = propagator.propagate(t0).unwrap();
+        let observer = Observer { lat: state.lat, lon: state.lon, alt_km: 0.0 };
+
+        let passes = find_passes(&propagator, observer, t0 - Duration::minutes(30), t0 + Duration::minutes(30), 0.0).unwrap();
+        assert_eq!(passes.len(), 1);
+        let pass = &passes[0];
+        assert!(pass.max_elevation > 89.5, "max elevation {}", pass.max_elevation);
+        assert!((pass.culmination.at - t0).num_milliseconds().abs() <= 2000);
+        assert!(pass.rise.elevation.abs() < 0.1 && pass.set.elevation.abs() < 0.1);
+        // An overhead pass rises and sets on opposite sides of the sky.
+        let az_diff = (pass.set.azimuth - pass.rise.azimuth).rem_euclid(360.0);
+        assert!((az_diff - 180.0).abs() < 3.0, "azimuth difference {}", az_diff);
+
+        // Time to sweep the arc between both horizons, ignoring Earth's rotation.
+        let half_arc = (WGS84_A / (WGS84_A + state.altitude_km)).acos();
+        let mean_motion = std::f64::consts::TAU * 15.721_253_91 / 86_400.0;
+        let expected_sec = 2.0 * half_arc / mean_motion;
+        let ratio = pass.duration_sec as f64 / expected_sec;
+        assert!((0.95..1.1).contains(&ratio), "duration {} s, expected about {:.0} s", pass.duration_sec, expected_sec);
+    }
+
+    #[test]
+    fn no_passes_beyond_the_ground_track_reach() {
+        let propagator = iss_2008();
+        let observer = Observer { lat: 80.0, lon: 0.0, alt_km: 0.0 };
+        let from = propagator.epoch();
+        assert!(find_passes(&propagator, observer, from, from + Duration::days(1), 0.0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn min_elevation_filters_low_passes() {
+        let propagator = iss_2008();
+        let observer = Observer { lat: 40.7, lon: -74.0, alt_km: 0.0 };
+        let (from, to) = (propagator.epoch(), propagator.epoch() + Duration::days(1));
+        let all = find_passes(&propagator, observer, from, to, 0.0).unwrap();
+        let high = find_passes(&propagator, observer, from, to, 40.0).unwrap();
+        assert!(!high.is_empty() && high.len() < all.len());
+        assert!(high.iter().all(|p| p.max_elevation >= 40.0));
+        for pair in all.windows(2) {
+            assert!(pair[0].rise.at < pair[0].culmination.at && pair[0].culmination.at < pair[0].set.at);
+            assert!(pair[0].set.at < pair[1].rise.at);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+/// Mean Earth radius in kilometers, matching the haversine helper.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+/// Beyond this interval the chord between two samples no longer follows the orbit,
+/// so the implied speed is not checked.
+const MAX_SPEED_CHECK_SEC: f64 = 900.0;
+
+/// Quality flag stored with every fetched sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleQuality {
+    Ok,
+    /// The payload could not be parsed into a valid position.
+    Invalid,
+    /// The upstream `timestamp` equals the previous sample's.
+    Duplicate,
+    /// The upstream `timestamp` is older than the previous sample's.
+    NonMonotonic,
+    /// The speed implied by the previous sample is far from the expected orbital speed.
+    SpeedOutlier,
+}
+
+impl SampleQuality {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SampleQuality::Ok => "ok",
+            SampleQuality::Invalid => "invalid",
+            SampleQuality::Duplicate => "duplicate",
+            SampleQuality::NonMonotonic => "non_monotonic",
+            SampleQuality::SpeedOutlier => "speed_outlier",
+        }
+    }
+}
+
+/// Limits used to judge the implied speed between two samples.
+#[derive(Debug, Clone, Copy)]
+pub struct SpeedCheck {
+    pub expected_kmh: f64,
+    /// Allowed relative deviation, e.g. `0.25` for ±25%.
+    pub tolerance: f64,
+}
+
+/// A position and time extracted from an upstream payload.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude_km: f64,
+    pub time: DateTime<Utc>,
+    /// Upstream `timestamp` (Unix seconds), if the provider sends one.
+    pub upstream_ts: Option<i64>,
+}
+
+impl Sample {
+    /// Reads a sample from a wheretheiss.at-style payload, falling back to `fetched_at` for time.
+    pub fn from_payload(payload: &Value, fetched_at: DateTime<Utc>) -> Option<Self> {
+        let num = |k: &str| {
+            let v = &payload[k];
+            v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+        };
+        let upstream_ts = num("timestamp").map(|t| t as i64);
+        let time = upstream_ts
+            .and_then(|t| DateTime::<Utc>::from_timestamp(t, 0))
+            .unwrap_or(fetched_at);
+        Some(Self {
+            lat: num("latitude")?,
+            lon: num("longitude")?,
+            altitude_km: num("altitude").unwrap_or(0.0),
+            time,
+            upstream_ts,
+        })
+    }
+}
+
+/// Judges a new sample against the previous good one. The implied speed is only checked
+/// when `check` is given.
+pub fn assess(prev: &Sample, current: &Sample, check: Option<SpeedCheck>, distance_km: f64) -> SampleQuality {
+    if let (Some(a), Some(b)) = (prev.upstream_ts, current.upstream_ts) {
+        if b == a {
+            return SampleQuality::Duplicate;
+        }
+        if b < a {
+            return SampleQuality::NonMonotonic;
+        }
+    }
+
+    let dt = (current.time - prev.time).num_milliseconds() as f64 / 1000.0;
+    if dt <= 0.0 {
+        return SampleQuality::NonMonotonic;
+    }
+    let Some(check) = check else {
+        return SampleQuality::Ok;
+    };
+    if dt <= MAX_SPEED_CHECK_SEC {
+        // Scale the ground distance up to the orbit to compare it with the orbital speed.
+        let orbit_scale = (EARTH_RADIUS_KM + current.altitude_km) / EARTH_RADIUS_KM;
+        let implied_kmh = distance_km * orbit_scale / dt * 3600.0;
+        if (implied_kmh - check.expected_kmh).abs() > check.expected_kmh * check.tolerance {
+            return SampleQuality::SpeedOutlier;
+        }
+    }
+    SampleQuality::Ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    const CHECK: SpeedCheck = SpeedCheck { expected_kmh: 27_600.0, tolerance: 0.25 };
+
+    fn sample(secs: i64, upstream_ts: Option<i64>) -> Sample {
+        Sample {
+            lat: 0.0,
+            lon: 0.0,
+            altitude_km: 420.0,
+            time: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap() + chrono::Duration::seconds(secs),
+            upstream_ts,
+        }
+    }
+
+    /// Ground distance covered in `secs` at the expected orbital speed.
+    fn orbital_km(secs: f64) -> f64 {
+        CHECK.expected_kmh / 3600.0 * secs * EARTH_RADIUS_KM / (EARTH_RADIUS_KM + 420.0)
+    }
+
+    #[test]
+    fn same_upstream_timestamp_is_duplicate() {
+        let q = assess(&sample(0, Some(1000)), &sample(120, Some(1000)), Some(CHECK), orbital_km(120.0));
+        assert_eq!(q, SampleQuality::Duplicate);
+    }
+
+    #[test]
+    fn older_timestamp_is_non_monotonic() {
+        let q = assess(&sample(0, Some(1000)), &sample(120, Some(900)), Some(CHECK), orbital_km(120.0));
+        assert_eq!(q, SampleQuality::NonMonotonic);
+        // Without upstream timestamps the sample times are compared.
+        let q = assess(&sample(120, None), &sample(60, None), Some(CHECK), orbital_km(60.0));
+        assert_eq!(q, SampleQuality::NonMonotonic);
+        let q = assess(&sample(120, None), &sample(120, None), Some(CHECK), 0.0);
+        assert_eq!(q, SampleQuality::NonMonotonic);
+    }
+
+    #[test]
+    fn implied_speed_is_checked_against_tolerance() {
+        let (prev, current) = (sample(0, None), sample(120, None));
+        assert_eq!(assess(&prev, &current, Some(CHECK), orbital_km(120.0)), SampleQuality::Ok);
+        assert_eq!(assess(&prev, &current, Some(CHECK), orbital_km(120.0) * 1.2), SampleQuality::Ok);
+        assert_eq!(assess(&prev, &current, Some(CHECK), orbital_km(120.0) * 1.3), SampleQuality::SpeedOutlier);
+        assert_eq!(assess(&prev, &current, Some(CHECK), orbital_km(120.0) * 0.7), SampleQuality::SpeedOutlier);
+        assert_eq!(assess(&prev, &current, Some(CHECK), 0.0), SampleQuality::SpeedOutlier);
+    }
+
+    #[test]
+    fn speed_is_not_checked_without_check_or_over_long_intervals() {
+        let q = assess(&sample(0, None), &sample(120, None), None, 0.0);
+        assert_eq!(q, SampleQuality::Ok);
+        let q = assess(&sample(0, None), &sample(3600, None), Some(CHECK), 0.0);
+        assert_eq!(q, SampleQuality::Ok);
+    }
+
+    #[test]
+    fn sample_from_payload() {
+        let fetched_at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let s = Sample::from_payload(&json!({ "latitude": "51.5", "longitude": -0.1, "timestamp": 1_714_564_800 }), fetched_at).unwrap();
+        assert_eq!((s.lat, s.lon, s.upstream_ts), (51.5, -0.1, Some(1_714_564_800)));
+        assert_eq!(s.time, fetched_at);
+        assert!(Sample::from_payload(&json!({ "bad": 1 }), fetched_at).is_none());
+    }
+}
@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::domain::orbit::{geodetic_to_ecef, julian_date, look_angles, teme_to_ecef, WGS84_A};
+
+/// One astronomical unit in kilometers.
+pub const AU_KM: f64 = 149_597_870.7;
+
+/// Lighting conditions of a satellite sample.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct Illumination {
+    /// The satellite is in sunlight (`false` means it is in Earth's shadow).
+    pub sunlit: bool,
+    /// Sun elevation in degrees at the ground point below the satellite.
+    pub ground_sun_elevation: f64,
+    pub subsolar_lat: f64,
+    pub subsolar_lon: f64,
+}
+
+/// Low-precision solar ecliptic longitude, obliquity and distance (AU) at `t`,
+/// after the Astronomical Almanac approximation (about 0.01° accuracy).
+fn sun_ecliptic(t: DateTime<Utc>) -> (f64, f64, f64) {
+    let n = julian_date(t) - 2_451_545.0;
+    let l = (280.460 + 0.985_647_4 * n).rem_euclid(360.0);
+    let g = (357.528 + 0.985_600_3 * n).rem_euclid(360.0).to_radians();
+    let lambda = (l + 1.915 * g.sin() + 0.020 * (2.0 * g).sin()).to_radians();
+    let epsilon = (23.439 - 0.000_000_4 * n).to_radians();
+    let r_au = 1.000_14 - 0.016_71 * g.cos() - 0.000_14 * (2.0 * g).cos();
+    (lambda, epsilon, r_au)
+}
+
+/// Earth-fixed position of the sun in kilometers.
+pub fn sun_ecef_km(t: DateTime<Utc>) -> [f64; 3] {
+    let (lambda, epsilon, r_au) = sun_ecliptic(t);
+    let r = r_au * AU_KM;
+    // The equatorial mean-of-date frame is close enough to TEME for shadow tests.
+    let eci = [
+        r * lambda.cos(),
+        r * epsilon.cos() * lambda.sin(),
+        r * epsilon.sin() * lambda.sin(),
+    ];
+    teme_to_ecef(eci, t)
+}
+
+/// Whether an Earth-fixed position is illuminated by the sun (cylindrical shadow model).
+pub fn is_sunlit(ecef_km: [f64; 3], t: DateTime<Utc>) -> bool {
+    let sun = sun_ecef_km(t);
+    let sun_norm = (sun[0] * sun[0] + sun[1] * sun[1] + sun[2] * sun[2]).sqrt();
+    let s = [sun[0] / sun_norm, sun[1] / sun_norm, sun[2] / sun_norm];
+    let along = ecef_km[0] * s[0] + ecef_km[1] * s[1] + ecef_km[2] * s[2];
+    if along >= 0.0 {
+        return true;
+    }
+    let perp = [
+        ecef_km[0] - along * s[0],
+        ecef_km[1] - along * s[1],
+        ecef_km[2] - along * s[2],
+    ];
+    (perp[0] * perp[0] + perp[1] * perp[1] + perp[2] * perp[2]).sqrt() > WGS84_A
+}
+
+/// Geocentric latitude and longitude (degrees) of the point where the sun is at the zenith.
+pub fn subsolar_point(t: DateTime<Utc>) -> (f64, f64) {
+    let s = sun_ecef_km(t);
+    let lat = s[2].atan2((s[0] * s[0] + s[1] * s[1]).sqrt()).to_degrees();
+    let lon = s[1].atan2(s[0]).to_degrees();
+    (lat, lon)
+}
+
+/// Lighting conditions of a satellite at geodetic `lat`/`lon` (degrees) and `alt_km` at `t`.
+pub fn illumination(lat: f64, lon: f64, alt_km: f64, t: DateTime<Utc>) -> Illumination {
+    let (subsolar_lat, subsolar_lon) = subsolar_point(t);
+    Illumination {
+        sunlit: is_sunlit(geodetic_to_ecef(lat, lon, alt_km), t),
+        ground_sun_elevation: look_angles(lat, lon, 0.0, sun_ecef_km(t)).elevation,
+        subsolar_lat,
+        subsolar_lon,
+    }
+}
+
+/// Builds a GeoJSON Feature whose polygon covers the night side of the Earth at `t`,
+/// sampled every `step_deg` degrees of longitude. The subsolar point is in its properties.
+pub fn terminator_geojson(t: DateTime<Utc>, step_deg: f64) -> Value {
+    let (sub_lat, sub_lon) = subsolar_point(t);
+    // At the equinoxes the terminator runs through the poles; keep tan() away from zero.
+    let decl = if sub_lat.abs() < 1e-6 { 1e-6_f64.copysign(sub_lat) } else { sub_lat }.to_radians();
+
+    let steps = (360.0 / step_deg).round().max(1.0) as usize;
+    let mut ring: Vec<Value> = (0..=steps)
+        .map(|i| {
+            let lon = -180.0 + 360.0 * i as f64 / steps as f64;
+            let hour_angle = (lon - sub_lon).to_radians();
+            let lat = (-hour_angle.cos() / decl.tan()).atan().to_degrees();
+            json!([lon, lat])
+        })
+        .collect();
+    // Close the ring around the pole that is in darkness.
+    let dark_pole = if sub_lat >= 0.0 { -90.0 } else { 90.0 };
+    ring.push(json!([180.0, dark_pole]));
+    ring.push(json!([-180.0, dark_pole]));
+    ring.push(ring[0].clone());
+
+    json!({
+        "type": "Feature",
+        "geometry": { "type": "Polygon", "coordinates": [ring] },
+        "properties": {
+            "at": t,
+            "subsolar_lat": sub_lat,
+            "subsolar_lon": sub_lon,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn lon_diff(a: f64, b: f64) -> f64 {
+        ((a - b + 540.0).rem_euclid(360.0) - 180.0).abs()
+    }
+
+    #[test]
+    fn subsolar_point_at_solstice_and_equinox() {
+        let (lat, lon) = subsolar_point(Utc.with_ymd_and_hms(2024, 6, 21, 12, 0, 0).unwrap());
+        assert!((lat - 23.43).abs() < 0.1, "solstice latitude {}", lat);
+        // The equation of time is under two minutes then, so the sun is near Greenwich at noon.
+        assert!(lon_diff(lon, 0.0) < 1.0, "solstice longitude {}", lon);
+
+        let (lat, _) = subsolar_point(Utc.with_ymd_and_hms(2024, 3, 20, 3, 6, 0).unwrap());
+        assert!(lat.abs() < 0.1, "equinox latitude {}", lat);
+    }
+
+    #[test]
+    fn sunlit_above_the_subsolar_point() {
+        let t = Utc.with_ymd_and_hms(2024, 6, 21, 12, 0, 0).unwrap();
+        let (lat, lon) = subsolar_point(t);
+        let at = illumination(lat, lon, 420.0, t);
+        assert!(at.sunlit);
+        assert!(at.ground_sun_elevation > 89.0, "sun elevation {}", at.ground_sun_elevation);
+    }
+
+    #[test]
+    fn eclipsed_above_the_antisolar_point() {
+        let t = Utc.with_ymd_and_hms(2024, 6, 21, 12, 0, 0).unwrap();
+        let (lat, lon) = subsolar_point(t);
+        let at = illumination(-lat, lon + 180.0, 420.0, t);
+        assert!(!at.sunlit);
+        assert!(at.ground_sun_elevation < -89.0, "sun elevation {}", at.ground_sun_elevation);
+    }
+
+    #[test]
+    fn shadow_is_a_cylinder_behind_the_earth() {
+        let t = Utc.with_ymd_and_hms(2024, 3, 20, 3, 6, 0).unwrap();
+        let (_, lon) = subsolar_point(t);
+        // Over the terminator the station is well outside the shadow.
+        assert!(illumination(0.0, lon + 90.0, 420.0, t).sunlit);
+        assert!(illumination(0.0, lon - 90.0, 420.0, t).sunlit);
+        // 150° from the subsolar point it is only 3400 km off the shadow axis.
+        assert!(!illumination(0.0, lon + 150.0, 420.0, t).sunlit);
+        // The umbra reaches far beyond any Earth orbit.
+        assert!(!is_sunlit(geodetic_to_ecef(0.0, lon + 180.0, 50_000.0), t));
+    }
+}
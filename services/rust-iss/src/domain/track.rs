@@ -0,0 +1,210 @@
+use chrono::{Duration, SecondsFormat};
+use serde_json::{json, Value};
+
+use crate::domain::models::IssHistoryPoint;
+
+/// Fastest plausible change of the ISS ground-track longitude. The track moves about 4°/min
+/// at the equator and faster near its turning latitudes.
+const MAX_LON_DEG_PER_MIN: f64 = 8.0;
+
+/// Splits a ground track into segments that never cross the antimeridian.
+/// At each crossing between adjacent samples the segment is closed at ±180° and the next one
+/// starts at ∓180°, with latitude, altitude and time interpolated at the crossing.
+/// Samples more than `max_gap` apart, or whose longitude jump is too big for the ISS, are
+/// not adjacent: a new segment starts there without interpolating anything.
+pub fn split_antimeridian(points: &[IssHistoryPoint], max_gap: Duration) -> Vec<Vec<IssHistoryPoint>> {
+    let mut segments: Vec<Vec<IssHistoryPoint>> = Vec::new();
+    let mut current: Vec<IssHistoryPoint> = Vec::new();
+
+    for p in points {
+        if let Some(prev) = current.last().cloned() {
+            let dt = p.fetched_at - prev.fetched_at;
+            let d_lon = p.lon - prev.lon;
+            let jump = if d_lon.abs() > 180.0 { 360.0 - d_lon.abs() } else { d_lon.abs() };
+            let max_jump = MAX_LON_DEG_PER_MIN * (dt.num_seconds() as f64 / 60.0).max(1.0);
+            if dt > max_gap || jump > max_jump {
+                segments.push(std::mem::take(&mut current));
+            } else if d_lon.abs() > 180.0 {
+                // Unwrap the next longitude so the segment between the two points is continuous.
+                let edge = if d_lon < 0.0 { 180.0 } else { -180.0 };
+                let unwrapped = p.lon + 2.0 * edge;
+                let f = (edge - prev.lon) / (unwrapped - prev.lon);
+                let crossing = |lon: f64| IssHistoryPoint {
+                    lat: prev.lat + (p.lat - prev.lat) * f,
+                    lon,
+                    altitude: prev.altitude.zip(p.altitude).map(|(a, b)| a + (b - a) * f),
+                    velocity: p.velocity,
+                    fetched_at: prev.fetched_at
+                        + Duration::milliseconds((dt.num_milliseconds() as f64 * f) as i64),
+                    quality: p.quality.clone(),
+                    illumination: None,
+                };
+                current.push(crossing(edge));
+                segments.push(std::mem::take(&mut current));
+                current.push(crossing(-edge));
+            }
+        }
+        current.push(p.clone());
+    }
+    if !current.is_empty() {
+        segments.push(current);
+    }
+    segments
+}
+
+fn altitude_m(p: &IssHistoryPoint) -> f64 {
+    p.altitude.unwrap_or(0.0) * 1000.0
+}
+
+fn timestamp(p: &IssHistoryPoint) -> String {
+    p.fetched_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Builds a GeoJSON FeatureCollection with one LineString feature per track segment.
+pub fn to_geojson(segments: &[Vec<IssHistoryPoint>]) -> Value {
+    let features: Vec<Value> = segments
+        .iter()
+        .enumerate()
+        .map(|(i, seg)| {
+            let coordinates: Vec<Value> = seg.iter().map(|p| json!([p.lon, p.lat, altitude_m(p)])).collect();
+            json!({
+                "type": "Feature",
+                "geometry": { "type": "LineString", "coordinates": coordinates },
+                "properties": {
+                    "name": "ISS",
+                    "segment": i,
+                    "from": seg.first().map(timestamp),
+                    "to": seg.last().map(timestamp),
+                    "points": seg.len(),
+                },
+            })
+        })
+        .collect();
+
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// Builds a KML document with one placemark per track segment.
+pub fn to_kml(segments: &[Vec<IssHistoryPoint>]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n<name>ISS ground track</name>\n",
+    );
+    for (i, seg) in segments.iter().enumerate() {
+        let coordinates: Vec<String> = seg
+            .iter()
+            .map(|p| format!("{},{},{}", p.lon, p.lat, altitude_m(p)))
+            .collect();
+        out.push_str(&format!(
+            "<Placemark>\n<name>ISS segment {}</name>\n<LineString>\n<altitudeMode>absolute</altitudeMode>\n\
+             <coordinates>{}</coordinates>\n</LineString>\n</Placemark>\n",
+            i,
+            coordinates.join(" ")
+        ));
+    }
+    out.push_str("</Document>\n</kml>\n");
+    out
+}
+
+/// Builds a GPX 1.1 document with one track segment per antimeridian-free segment.
+pub fn to_gpx(segments: &[Vec<IssHistoryPoint>]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <gpx version=\"1.1\" creator=\"rust_iss\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n<trk>\n<name>ISS</name>\n",
+    );
+    for seg in segments {
+        out.push_str("<trkseg>\n");
+        for p in seg {
+            out.push_str(&format!(
+                "<trkpt lat=\"{}\" lon=\"{}\"><ele>{}</ele><time>{}</time></trkpt>\n",
+                p.lat,
+                p.lon,
+                altitude_m(p),
+                timestamp(p)
+            ));
+        }
+        out.push_str("</trkseg>\n");
+    }
+    out.push_str("</trk>\n</gpx>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, h, m, 0).unwrap()
+    }
+
+    fn point(lat: f64, lon: f64, fetched_at: DateTime<Utc>) -> IssHistoryPoint {
+        IssHistoryPoint {
+            lat,
+            lon,
+            altitude: Some(420.0),
+            velocity: None,
+            fetched_at,
+            quality: "ok".to_string(),
+            illumination: None,
+        }
+    }
+
+    fn lons(segment: &[IssHistoryPoint]) -> Vec<f64> {
+        segment.iter().map(|p| p.lon).collect()
+    }
+
+    #[test]
+    fn eastward_crossing_is_interpolated() {
+        let track = [point(10.0, 170.0, at(12, 0)), point(12.0, -170.0, at(12, 4))];
+        let segments = split_antimeridian(&track, Duration::minutes(6));
+        assert_eq!(segments.len(), 2);
+        assert_eq!(lons(&segments[0]), vec![170.0, 180.0]);
+        assert_eq!(lons(&segments[1]), vec![-180.0, -170.0]);
+        assert!((segments[0][1].lat - 11.0).abs() < 1e-9);
+        assert_eq!(segments[0][1].fetched_at, at(12, 2));
+        assert_eq!(segments[1][0].lat, segments[0][1].lat);
+    }
+
+    #[test]
+    fn westward_crossing_is_interpolated() {
+        let track = [point(-5.0, -170.0, at(12, 0)), point(-1.0, 170.0, at(12, 4))];
+        let segments = split_antimeridian(&track, Duration::minutes(6));
+        assert_eq!(segments.len(), 2);
+        assert_eq!(lons(&segments[0]), vec![-170.0, -180.0]);
+        assert_eq!(lons(&segments[1]), vec![180.0, 170.0]);
+        assert!((segments[1][0].lat + 3.0).abs() < 1e-9);
+        assert_eq!(segments[0][1].fetched_at, at(12, 2));
+    }
+
+    #[test]
+    fn implausible_jump_starts_new_segment_without_crossing() {
+        let track = [point(5.0, -100.0, at(12, 18)), point(16.0, 100.0, at(12, 20))];
+        let segments = split_antimeridian(&track, Duration::minutes(6));
+        assert_eq!(segments.len(), 2);
+        assert_eq!(lons(&segments[0]), vec![-100.0]);
+        assert_eq!(lons(&segments[1]), vec![100.0]);
+    }
+
+    #[test]
+    fn time_gap_starts_new_segment_without_crossing() {
+        let track = [
+            point(10.0, 160.0, at(12, 0)),
+            point(11.0, 170.0, at(12, 2)),
+            point(12.0, -170.0, at(13, 30)),
+            point(13.0, -160.0, at(13, 32)),
+        ];
+        let segments = split_antimeridian(&track, Duration::minutes(6));
+        assert_eq!(segments.len(), 2);
+        assert_eq!(lons(&segments[0]), vec![160.0, 170.0]);
+        assert_eq!(lons(&segments[1]), vec![-170.0, -160.0]);
+    }
+
+    #[test]
+    fn continuous_track_stays_in_one_segment() {
+        let track = [point(0.0, 10.0, at(12, 0)), point(1.0, 18.0, at(12, 2)), point(2.0, 26.0, at(12, 4))];
+        let segments = split_antimeridian(&track, Duration::minutes(6));
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].len(), 3);
+    }
+}
@@ -1,5 +1,5 @@
-use chrono::{DateTime, NaiveDateTime, Utc, TimeZone};
-use serde_json::Value;
+use chrono::Utc;
+use serde_json::{json, Value};
 
 /// Picks the first non-empty string value from a JSON object using a list of possible keys.
 pub fn s_pick(v: &Value, keys: &[&str]) -> Option<String> {
@@ -17,26 +17,47 @@ pub fn s_pick(v: &Value, keys: &[&str]) -> Option<String> {
     None
 }
 
-/// Picks and parses the first valid timestamp from a JSON object using a list of possible keys.
-/// Handles RFC3339, "Y-m-d H:M:S", and Unix timestamps.
-pub fn t_pick(v: &Value, keys: &[&str]) -> Option<DateTime<Utc>> {
-    for k in keys {
-        if let Some(x) = v.get(*k) {
-            if let Some(s) = x.as_str() {
-                if let Ok(dt) = s.parse::<DateTime<Utc>>() {
-                    return Some(dt);
+/// Lists the differences between two JSON documents as `{ path, op, from, to }` entries,
+/// where `path` is a JSON Pointer and `op` is `added`, `removed` or `changed`.
+pub fn json_diff(before: &Value, after: &Value) -> Vec<Value> {
+    let mut out = Vec::new();
+    diff_at("", before, after, &mut out);
+    out
+}
+
+fn diff_at(path: &str, before: &Value, after: &Value, out: &mut Vec<Value>) {
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (k, av) in a {
+                let p = format!("{}/{}", path, k.replace('~', "~0").replace('/', "~1"));
+                match b.get(k) {
+                    Some(bv) => diff_at(&p, av, bv, out),
+                    None => out.push(json!({ "path": p, "op": "removed", "from": av, "to": null })),
                 }
-                if let Ok(ndt) = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
-                    return Some(Utc.from_utc_datetime(&ndt));
+            }
+            for (k, bv) in b {
+                if !a.contains_key(k) {
+                    let p = format!("{}/{}", path, k.replace('~', "~0").replace('/', "~1"));
+                    out.push(json!({ "path": p, "op": "added", "from": null, "to": bv }));
                 }
-            } else if let Some(n) = x.as_i64() {
-                if let Some(dt) = Utc.timestamp_opt(n, 0).single() {
-                    return Some(dt);
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                let p = format!("{}/{}", path, i);
+                match (a.get(i), b.get(i)) {
+                    (Some(av), Some(bv)) => diff_at(&p, av, bv, out),
+                    (Some(av), None) => out.push(json!({ "path": p, "op": "removed", "from": av, "to": null })),
+                    (None, Some(bv)) => out.push(json!({ "path": p, "op": "added", "from": null, "to": bv })),
+                    (None, None) => {}
                 }
             }
         }
+        _ if before != after => {
+            out.push(json!({ "path": path, "op": "changed", "from": before, "to": after }));
+        }
+        _ => {}
     }
-    None
 }
 
 /// Returns a tuple of (start_date, end_date) as strings for the last N days.
@@ -45,3 +66,64 @@ pub fn last_days(n: i64) -> (String, String) {
     let from = to - chrono::Duration::days(n);
     (from.to_string(), to.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_documents_have_no_diff() {
+        let doc = json!({ "a": 1, "b": [1, { "c": null }] });
+        assert!(json_diff(&doc, &doc).is_empty());
+    }
+
+    #[test]
+    fn added_removed_and_changed_keys() {
+        let diff = json_diff(&json!({ "a": 1, "b": "x" }), &json!({ "b": "y", "c": true }));
+        assert_eq!(
+            diff,
+            vec![
+                json!({ "path": "/a", "op": "removed", "from": 1, "to": null }),
+                json!({ "path": "/b", "op": "changed", "from": "x", "to": "y" }),
+                json!({ "path": "/c", "op": "added", "from": null, "to": true }),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_objects_use_json_pointer_paths() {
+        let diff = json_diff(
+            &json!({ "study": { "title": "Old", "a/b": 1 } }),
+            &json!({ "study": { "title": "New", "a/b": 2 } }),
+        );
+        assert_eq!(
+            diff,
+            vec![
+                json!({ "path": "/study/a~1b", "op": "changed", "from": 1, "to": 2 }),
+                json!({ "path": "/study/title", "op": "changed", "from": "Old", "to": "New" }),
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_are_compared_by_index() {
+        let diff = json_diff(&json!({ "tags": ["a", "b", "c"] }), &json!({ "tags": ["a", "x"] }));
+        assert_eq!(
+            diff,
+            vec![
+                json!({ "path": "/tags/1", "op": "changed", "from": "b", "to": "x" }),
+                json!({ "path": "/tags/2", "op": "removed", "from": "c", "to": null }),
+            ]
+        );
+        let diff = json_diff(&json!([]), &json!([{ "id": 1 }]));
+        assert_eq!(diff, vec![json!({ "path": "/0", "op": "added", "from": null, "to": { "id": 1 } })]);
+    }
+
+    #[test]
+    fn type_change_is_reported_whole() {
+        let diff = json_diff(&json!({ "a": { "b": 1 } }), &json!({ "a": [1] }));
+        assert_eq!(diff, vec![json!({ "path": "/a", "op": "changed", "from": { "b": 1 }, "to": [1] })]);
+        let diff = json_diff(&json!(1), &json!(2));
+        assert_eq!(diff, vec![json!({ "path": "", "op": "changed", "from": 1, "to": 2 })]);
+    }
+}
@@ -1,4 +1,7 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_valid::validation::Errors;
+use serde_valid::Validate as _;
 use serde_valid_derive::Validate;
 
 #[derive(Debug, Deserialize, Validate)]
@@ -10,3 +13,91 @@ pub struct OsdrItemValidation {
     #[validate(min_length = 1)]
     pub status: Option<String>,
 }
+
+/// One failed check on an upstream item. `path` is a JSON Pointer into the item,
+/// empty when the check concerns the item as a whole.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub fn new(path: &str, message: impl Into<String>) -> Self {
+        Self { path: path.to_string(), message: message.into() }
+    }
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl OsdrItemValidation {
+    /// Deserializes and validates an upstream item, listing every failed check.
+    pub fn check(item: &Value) -> Result<(), Vec<ValidationIssue>> {
+        let parsed: Self = serde_json::from_value(item.clone())
+            .map_err(|e| vec![ValidationIssue::new("", e.to_string())])?;
+        parsed.validate().map_err(|errors| {
+            let mut issues = Vec::new();
+            flatten("", &errors, &mut issues);
+            issues
+        })
+    }
+}
+
+// --- Private Helper Functions ---
+
+fn flatten(path: &str, errors: &Errors, out: &mut Vec<ValidationIssue>) {
+    let mut push_all = |errs: &[serde_valid::validation::Error]| {
+        out.extend(errs.iter().map(|e| ValidationIssue::new(path, e.to_string())));
+    };
+    match errors {
+        Errors::Object(o) => {
+            push_all(&o.errors);
+            for (key, e) in &o.properties {
+                flatten(&format!("{}/{}", path, key), e, out);
+            }
+        }
+        Errors::Array(a) => {
+            push_all(&a.errors);
+            for (index, e) in &a.items {
+                flatten(&format!("{}/{}", path, index), e, out);
+            }
+        }
+        Errors::NewType(errs) => push_all(errs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn valid_item_passes() {
+        assert!(OsdrItemValidation::check(&json!({ "dataset_id": "OSD-1", "title": "T" })).is_ok());
+        assert!(OsdrItemValidation::check(&json!({ "id": 1 })).is_ok());
+    }
+
+    #[test]
+    fn empty_field_is_reported_at_its_path() {
+        let issues = OsdrItemValidation::check(&json!({ "dataset_id": "OSD-1", "title": "" })).unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "/title");
+        assert!(issues[0].to_string().starts_with("/title: "));
+    }
+
+    #[test]
+    fn deserialization_error_has_empty_path() {
+        let issues = OsdrItemValidation::check(&json!({ "title": 5 })).unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "");
+        assert_eq!(issues[0].to_string(), issues[0].message);
+    }
+}
@@ -0,0 +1,62 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde_json::Value;
+use tracing::info;
+
+use crate::domain::error::ApiError;
+use crate::domain::models::{AppState, Geofence, GeofenceEventsQuery, NewGeofence, ISS_NORAD_ID};
+use crate::services::geofence_service::GeofenceService;
+
+const EVENTS_DEFAULT_DAYS: i64 = 7;
+const EVENTS_DEFAULT_LIMIT: i64 = 100;
+const EVENTS_MAX_LIMIT: i64 = 1000;
+
+/// Registers a new circle or polygon geofence.
+pub async fn create_geofence(
+    State(state): State<AppState>,
+    Json(body): Json<NewGeofence>,
+) -> Result<Json<Geofence>, ApiError> {
+    GeofenceService::validate(&body).map_err(ApiError::new_bad_request)?;
+    let geofence = state.geofence_service.create(&body).await.map_err(ApiError::from)?;
+    info!("Created geofence {} '{}'", geofence.id, geofence.name);
+    Ok(Json(geofence))
+}
+
+/// Lists all registered geofences.
+pub async fn list_geofences(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    let items = state.geofence_service.list().await.map_err(ApiError::from)?;
+    Ok(Json(serde_json::json!({ "items": items })))
+}
+
+/// Deletes a geofence together with its recorded events.
+pub async fn delete_geofence(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    if !state.geofence_service.delete(id).await.map_err(ApiError::from)? {
+        return Err(ApiError::new_not_found(format!("geofence {} not found", id)));
+    }
+    Ok(Json(serde_json::json!({ "deleted": id })))
+}
+
+/// Lists ISS enter/exit events, newest first (defaults to the last 7 days).
+pub async fn iss_geofence_events(
+    Query(q): Query<GeofenceEventsQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let to = q.to.unwrap_or_else(Utc::now);
+    let from = q.from.unwrap_or(to - Duration::days(EVENTS_DEFAULT_DAYS));
+    if from > to {
+        return Err(ApiError::new_bad_request("`from` must not be after `to`".to_string()));
+    }
+    let limit = q.limit.unwrap_or(EVENTS_DEFAULT_LIMIT).clamp(1, EVENTS_MAX_LIMIT);
+
+    let items = state.geofence_service
+        .events(ISS_NORAD_ID, q.geofence_id, from, to, limit)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(serde_json::json!({ "count": items.len(), "items": items })))
+}
@@ -1,18 +1,91 @@
+use std::convert::Infallible;
+
 use axum::{
-    extract::State,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::header,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
+use chrono::{DateTime, Duration, Utc};
 use serde_json::Value;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
 
 use crate::domain::models::AppState;
 use crate::domain::error::ApiError;
-use crate::domain::models::Trend;
-use tracing::info;
+use crate::domain::models::{
+    IssFetchLog, IssHistoryPoint, IssHistoryQuery, IssPassesQuery, IssPositionResponse,
+    IssPredictQuery, IssTrendQuery, Trend, ISS_NORAD_ID,
+};
+use crate::domain::passes::Observer;
+use crate::domain::solar::illumination;
+use crate::domain::track::{split_antimeridian, to_geojson, to_gpx, to_kml};
+use tracing::{info, warn};
+
+const HISTORY_DEFAULT_HOURS: i64 = 24;
+const HISTORY_DEFAULT_LIMIT: i64 = 1000;
+const HISTORY_MAX_LIMIT: i64 = 10000;
+const PASSES_DEFAULT_DAYS: i64 = 3;
+const PASSES_MAX_DAYS: i64 = 10;
+const PASSES_DEFAULT_MIN_ELEVATION: f64 = 10.0;
 
 /// Gets the most recent ISS log.
 pub async fn last_iss(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
     info!("Received request for last ISS position");
-    let log_opt = state.iss_service.get_last_iss().await.map_err(ApiError::from)?;
+    last_for(&state, ISS_NORAD_ID).await
+}
+
+/// Gets the most recent validated ISS position in the stable v2 shape.
+pub async fn last_iss_v2(State(state): State<AppState>) -> Result<Json<IssPositionResponse>, ApiError> {
+    info!("Received request for last ISS position (v2)");
+    last_position_for(&state, ISS_NORAD_ID).await
+}
+
+/// Triggers a new fetch of ISS data and returns the latest log.
+pub async fn trigger_iss(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    trigger_for(&state, ISS_NORAD_ID).await
+}
+
+/// Streams every newly stored ISS row as a Server-Sent Event named `iss`.
+pub async fn iss_stream(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    stream_for(&state, ISS_NORAD_ID)
+}
+
+/// Streams every newly stored ISS row as a JSON text message over a WebSocket.
+pub async fn iss_ws(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    ws_for(ws, &state, ISS_NORAD_ID)
+}
+
+/// Calculates and returns the movement trend of the ISS over a window of samples.
+pub async fn iss_trend(
+    Query(q): Query<IssTrendQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Trend>, ApiError> {
+    info!("Received request for ISS trend");
+    trend_for(&state, ISS_NORAD_ID, &q).await
+}
+
+/// Returns the ISS track for a time range (`from`/`to` as RFC3339, defaults to the last 24h).
+pub async fn iss_history(
+    Query(q): Query<IssHistoryQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    info!("Received request for ISS history");
+    history_for(&state, ISS_NORAD_ID, &q).await
+}
+
+// --- Satellite-generic implementations shared with `handlers::satellites` ---
+
+pub(crate) async fn last_for(state: &AppState, satellite_id: i64) -> Result<Json<Value>, ApiError> {
+    let log_opt = state.iss_service.get_last(satellite_id).await.map_err(ApiError::from)?;
 
     match log_opt {
         Some(log) => Ok(Json(log)),
@@ -20,16 +93,217 @@ pub async fn last_iss(State(state): State<AppState>) -> Result<Json<Value>, ApiE
     }
 }
 
-/// Triggers a new fetch of ISS data and returns the latest log.
-pub async fn trigger_iss(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
-    state.iss_service.fetch_and_store_iss().await.map_err(ApiError::from)?;
-    // Call the other handler to avoid duplicating logic
-    last_iss(State(state)).await
+pub(crate) async fn last_position_for(
+    state: &AppState,
+    satellite_id: i64,
+) -> Result<Json<IssPositionResponse>, ApiError> {
+    match state.iss_service.get_last_position(satellite_id).await.map_err(ApiError::from)? {
+        Some(position) => Ok(Json(position)),
+        None => Err(ApiError::new_not_found(format!("no position for satellite {} yet", satellite_id))),
+    }
 }
 
-/// Calculates and returns the movement trend of the ISS.
-pub async fn iss_trend(State(state): State<AppState>) -> Result<Json<Trend>, ApiError> {
-    info!("Received request for ISS trend");
-    let trend = state.iss_service.get_trend().await.map_err(ApiError::from)?;
+pub(crate) async fn trigger_for(state: &AppState, satellite_id: i64) -> Result<Json<Value>, ApiError> {
+    state.iss_service.fetch_and_store(satellite_id).await.map_err(ApiError::from)?;
+    last_for(state, satellite_id).await
+}
+
+pub(crate) fn stream_for(
+    state: &AppState,
+    satellite_id: i64,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    info!("New SSE subscriber for satellite {}", satellite_id);
+    let stream = BroadcastStream::new(state.iss_service.subscribe()).filter_map(move |msg| match msg {
+        Ok(log) if log.satellite_id == satellite_id => {
+            Event::default().event("iss").json_data(&log).ok().map(Ok)
+        }
+        // Other satellites are filtered out; a lagging client simply skips the rows it missed.
+        _ => None,
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+pub(crate) fn ws_for(ws: WebSocketUpgrade, state: &AppState, satellite_id: i64) -> Response {
+    info!("New WebSocket subscriber for satellite {}", satellite_id);
+    let rx = state.iss_service.subscribe();
+    ws.on_upgrade(move |socket| forward_to_socket(socket, rx, satellite_id))
+}
+
+async fn forward_to_socket(
+    mut socket: WebSocket,
+    mut rx: broadcast::Receiver<IssFetchLog>,
+    satellite_id: i64,
+) {
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(log) if log.satellite_id != satellite_id => {}
+                Ok(log) => {
+                    let Ok(text) = serde_json::to_string(&log) else { continue };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => warn!("WebSocket subscriber skipped {} rows", skipped),
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+        }
+    }
+}
+
+pub(crate) async fn trend_for(
+    state: &AppState,
+    satellite_id: i64,
+    q: &IssTrendQuery,
+) -> Result<Json<Trend>, ApiError> {
+    let trend = state.iss_service
+        .get_trend(satellite_id, q.samples, q.minutes, q.exclude_flagged.unwrap_or(true))
+        .await
+        .map_err(ApiError::from)?;
     Ok(Json(trend))
 }
+
+pub(crate) async fn history_for(
+    state: &AppState,
+    satellite_id: i64,
+    q: &IssHistoryQuery,
+) -> Result<Json<Value>, ApiError> {
+    let (from, to, limit) = resolve_range(q)?;
+    let items = state.iss_service
+        .get_history(satellite_id, from, to, limit, q.exclude_flagged.unwrap_or(true))
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(serde_json::json!({
+        "satellite_id": satellite_id,
+        "from": from,
+        "to": to,
+        "count": items.len(),
+        "items": items,
+    })))
+}
+
+/// Reports the `iss_fetch_log` retention policy and how much it has pruned.
+pub async fn iss_retention(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    info!("Received request for ISS retention stats");
+    let stats = state.retention_service.stats().await.map_err(ApiError::from)?;
+    Ok(Json(stats))
+}
+
+/// Exports the ISS ground track as a GeoJSON FeatureCollection.
+pub async fn iss_export_geojson(
+    Query(q): Query<IssHistoryQuery>,
+    State(state): State<AppState>,
+) -> Result<Response, ApiError> {
+    let segments = export_segments(&q, &state).await?;
+    let body = serde_json::to_string(&to_geojson(&segments)).map_err(anyhow::Error::from)?;
+    Ok(attachment("application/geo+json", "iss_track.geojson", body))
+}
+
+/// Exports the ISS ground track as a KML document.
+pub async fn iss_export_kml(
+    Query(q): Query<IssHistoryQuery>,
+    State(state): State<AppState>,
+) -> Result<Response, ApiError> {
+    let segments = export_segments(&q, &state).await?;
+    Ok(attachment("application/vnd.google-earth.kml+xml", "iss_track.kml", to_kml(&segments)))
+}
+
+/// Exports the ISS ground track as a GPX track.
+pub async fn iss_export_gpx(
+    Query(q): Query<IssHistoryQuery>,
+    State(state): State<AppState>,
+) -> Result<Response, ApiError> {
+    let segments = export_segments(&q, &state).await?;
+    Ok(attachment("application/gpx+xml", "iss_track.gpx", to_gpx(&segments)))
+}
+
+/// Loads the track for a history query and splits it at the antimeridian and at gaps of
+/// more than three sampling intervals: the polling interval, or the rollup bucket when the
+/// range reaches past the retention window.
+async fn export_segments(
+    q: &IssHistoryQuery,
+    state: &AppState,
+) -> Result<Vec<Vec<IssHistoryPoint>>, ApiError> {
+    info!("Received request for ISS track export");
+    let (from, to, limit) = resolve_range(q)?;
+    let points = state.iss_service
+        .get_history(ISS_NORAD_ID, from, to, limit, q.exclude_flagged.unwrap_or(true))
+        .await
+        .map_err(ApiError::from)?;
+    let rolled_up = from < Utc::now() - Duration::days(state.retention_days);
+    let interval = if rolled_up { (state.rollup_minutes * 60).max(state.every_iss as i64) } else { state.every_iss as i64 };
+    let max_gap = Duration::seconds(interval * 3);
+    Ok(split_antimeridian(&points, max_gap))
+}
+
+fn attachment(content_type: &'static str, filename: &str, body: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Predicts the ISS position at `at` (RFC3339, defaults to now) from the latest TLE.
+pub async fn iss_predict(
+    Query(q): Query<IssPredictQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let at = q.at.unwrap_or_else(Utc::now);
+    let prediction = state.orbit_service.predict(at).await.map_err(ApiError::from)?;
+
+    match prediction {
+        Some((position, tle_epoch)) => Ok(Json(serde_json::json!({
+            "norad_id": ISS_NORAD_ID,
+            "tle_epoch": tle_epoch,
+            "illumination": illumination(position.lat, position.lon, position.altitude_km, at),
+            "position": position,
+        }))),
+        None => Err(ApiError::new_not_found("no TLE data available yet".to_string())),
+    }
+}
+
+/// Predicts ISS passes over an observer (`lat`/`lon` in degrees, `alt` in meters).
+pub async fn iss_passes(
+    Query(q): Query<IssPassesQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    if !(-90.0..=90.0).contains(&q.lat) || !(-180.0..=180.0).contains(&q.lon) {
+        return Err(ApiError::new_bad_request("`lat` must be within ±90 and `lon` within ±180".to_string()));
+    }
+    let days = q.days.unwrap_or(PASSES_DEFAULT_DAYS).clamp(1, PASSES_MAX_DAYS);
+    let min_elevation = q.min_elevation.unwrap_or(PASSES_DEFAULT_MIN_ELEVATION);
+    let observer = Observer { lat: q.lat, lon: q.lon, alt_km: q.alt.unwrap_or(0.0) / 1000.0 };
+
+    let passes = state.orbit_service
+        .predict_passes(observer, days, min_elevation)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::new_not_found("no TLE data available yet".to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "observer": { "lat": q.lat, "lon": q.lon, "alt": q.alt.unwrap_or(0.0) },
+        "days": days,
+        "count": passes.len(),
+        "passes": passes,
+    })))
+}
+
+/// Applies defaults to the history query parameters and validates them.
+fn resolve_range(q: &IssHistoryQuery) -> Result<(DateTime<Utc>, DateTime<Utc>, i64), ApiError> {
+    let to = q.to.unwrap_or_else(Utc::now);
+    let from = q.from.unwrap_or(to - Duration::hours(HISTORY_DEFAULT_HOURS));
+    if from > to {
+        return Err(ApiError::new_bad_request("`from` must not be after `to`".to_string()));
+    }
+    let limit = q.limit.unwrap_or(HISTORY_DEFAULT_LIMIT).clamp(1, HISTORY_MAX_LIMIT);
+    Ok((from, to, limit))
+}
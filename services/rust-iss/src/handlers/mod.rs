@@ -1,4 +1,6 @@
 pub mod health;
 pub mod iss;
 pub mod osdr;
-pub mod space;
\ No newline at end of file
+pub mod space;
+pub mod satellites;
+pub mod geofence;
\ No newline at end of file
@@ -1,35 +1,238 @@
 use axum::{
-    extract::State,
+    extract::{Path, Query, State},
     Json,
 };
 use serde_json::Value;
 use tracing::info;
 
-use crate::domain::models::AppState;
+use crate::domain::models::{
+    AppState, OsdrFilter, OsdrHistoryQuery, OsdrListQuery, OsdrRejectedQuery, OsdrRevalidation,
+    OsdrSearchQuery, OsdrSort, OsdrSyncQuery, OsdrSyncRun,
+};
 use crate::domain::error::ApiError;
+use crate::domain::osdr::normalize;
+use crate::domain::utils::json_diff;
+
+const LIST_MAX_PER_PAGE: i64 = 100;
+const LIST_MAX_PAGE: i64 = 1_000_000;
 
-/// Asynchronously triggers a sync of the OSDR data.
-pub async fn osdr_sync(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+/// Asynchronously triggers a sync of the OSDR data. `full=true` ignores the sync watermark.
+/// Returns the run ID to poll at `/osdr/sync/:run_id`, which is the current run's if one
+/// is already in progress.
+pub async fn osdr_sync(
+    Query(q): Query<OsdrSyncQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
     let service = state.osdr_service.clone();
+    let full = q.full.unwrap_or(false);
+    let (run_id, started) = service.start_sync(full).await.map_err(ApiError::from)?;
+    if !started {
+        return Ok(Json(serde_json::json!({
+            "message": "OSDR sync already in progress.",
+            "run_id": run_id,
+        })));
+    }
     tokio::spawn(async move {
-        info!("Starting background OSDR sync...");
-        if let Ok(count) = service.fetch_and_store_osdr().await {
-            info!("OSDR background sync completed. Upserted {} records.", count);
-        } else {
-            info!("OSDR background sync failed.");
+        info!("Starting background OSDR sync run {}...", run_id);
+        match service.run_sync(run_id, full).await {
+            Ok(summary) => info!("OSDR sync run {} completed. Upserted {} records.", run_id, summary.written),
+            Err(e) => info!("OSDR sync run {} failed: {:?}", run_id, e),
         }
     });
 
-    Ok(Json(serde_json::json!({ "message": "OSDR sync triggered in background." })))
+    Ok(Json(serde_json::json!({
+        "message": "OSDR sync triggered in background.",
+        "run_id": run_id,
+    })))
+}
+
+/// Status and counters of one OSDR sync run.
+pub async fn osdr_sync_status(
+    Path(run_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<OsdrSyncRun>, ApiError> {
+    let run = state.osdr_service.get_run(run_id).await.map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::new_not_found(format!("OSDR sync run {} not found", run_id)))?;
+    Ok(Json(run))
+}
+
+/// Lists OSDR items page by page, with optional filters and sort order.
+pub async fn osdr_list(
+    Query(q): Query<OsdrListQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let sort = match q.sort.as_deref() {
+        Some(s) => OsdrSort::parse(s)
+            .ok_or_else(|| ApiError::new_bad_request(format!("unknown sort `{}`", s)))?,
+        None => OsdrSort::default(),
+    };
+    let withdrawn = match q.withdrawn.as_deref().unwrap_or("exclude") {
+        "exclude" => Some(false),
+        "only" => Some(true),
+        "include" => None,
+        other => {
+            return Err(ApiError::new_bad_request(format!(
+                "`withdrawn` must be exclude, only or include, got `{}`",
+                other
+            )))
+        }
+    };
+    let (page, per_page, offset) = paging(q.page, q.per_page, state.osdr_list_limit);
+    let filter = OsdrFilter {
+        status: q.status.filter(|s| !s.is_empty()),
+        updated_since: q.updated_since,
+        q: q.q.filter(|s| !s.trim().is_empty()),
+        withdrawn,
+    };
+
+    let (items, total) = state.osdr_repo
+        .list(&filter, sort, per_page, offset)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(serde_json::json!({
+        "items": items,
+        "page": page,
+        "per_page": per_page,
+        "total": total,
+        "pages": (total + per_page - 1) / per_page,
+    })))
+}
+
+/// Searches OSDR titles and descriptions, best matches first, with highlighted snippets.
+pub async fn osdr_search(
+    Query(q): Query<OsdrSearchQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let query = q.q.as_deref().map(str::trim).unwrap_or_default();
+    if query.is_empty() {
+        return Err(ApiError::new_bad_request("`q` is required".to_string()));
+    }
+    let (page, per_page, offset) = paging(q.page, q.per_page, state.osdr_list_limit);
+
+    let (items, total) = state.osdr_repo
+        .search(query, per_page, offset)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(serde_json::json!({
+        "query": query,
+        "items": items,
+        "page": page,
+        "per_page": per_page,
+        "total": total,
+        "pages": (total + per_page - 1) / per_page,
+    })))
 }
 
-/// Lists the most recent OSDR items.
-pub async fn osdr_list(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
-    let limit = std::env::var("OSDR_LIST_LIMIT")
-        .ok()
-        .and_then(|s| s.parse::<i64>().ok())
-        .unwrap_or(20);
+/// Dataset counts by status, organism, mission, assay type and month of last update.
+pub async fn osdr_stats(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    let stats = state.osdr_service.stats().await.map_err(ApiError::from)?;
+    Ok(Json(stats))
+}
+
+/// Maps a sample upstream page or item with the current field-mapping rules, without storing it.
+pub async fn osdr_mapping_dry_run(
+    State(state): State<AppState>,
+    Json(sample): Json<Value>,
+) -> Result<Json<Value>, ApiError> {
+    let report = state.osdr_service.dry_run(&sample).await.map_err(ApiError::from)?;
+    Ok(Json(report))
+}
 
-    let items = state.osdr_repo.list(limit).await.map_err(ApiError::from)?;
-    Ok(Json(serde_json::json!({ "items": items })))
-}
\ No newline at end of file
+/// Lists quarantined OSDR items with the validation errors that rejected them.
+pub async fn osdr_rejected(
+    Query(q): Query<OsdrRejectedQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let (page, per_page, offset) = paging(q.page, q.per_page, state.osdr_list_limit);
+    let (items, total) = state.osdr_service
+        .rejected(per_page, offset)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(serde_json::json!({
+        "items": items,
+        "page": page,
+        "per_page": per_page,
+        "total": total,
+        "pages": (total + per_page - 1) / per_page,
+    })))
+}
+
+/// Re-checks quarantined OSDR items against the current rules, storing those that now pass.
+pub async fn osdr_revalidate(State(state): State<AppState>) -> Result<Json<OsdrRevalidation>, ApiError> {
+    let result = state.osdr_service.revalidate_rejected().await.map_err(ApiError::from)?;
+    Ok(Json(result))
+}
+
+/// Gets a single dataset with normalized fields extracted from its raw record.
+pub async fn osdr_detail(
+    Path(dataset_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    info!("Received request for OSDR dataset {}", dataset_id);
+    let item = state.osdr_repo.get(&dataset_id).await.map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::new_not_found(format!("dataset {} not found", dataset_id)))?;
+    let normalized = normalize(&item["raw"]);
+    Ok(Json(serde_json::json!({ "item": item, "normalized": normalized })))
+}
+
+/// Lists the stored versions of a dataset, each with its changes against the previous version.
+pub async fn osdr_history(
+    Path(dataset_id): Path<String>,
+    Query(q): Query<OsdrHistoryQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    info!("Received request for history of OSDR dataset {}", dataset_id);
+    let versions = state.osdr_repo.list_versions(&dataset_id).await.map_err(ApiError::from)?;
+    if versions.is_empty() {
+        return Err(ApiError::new_not_found(format!("dataset {} not found", dataset_id)));
+    }
+
+    let items: Vec<Value> = versions
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let prev = i.checked_sub(1).map(|p| &versions[p]);
+            let status_change = prev
+                .filter(|p| p.status != v.status)
+                .map(|p| serde_json::json!({ "from": p.status, "to": v.status }));
+            serde_json::json!({
+                "version": v.version,
+                "content_hash": v.content_hash,
+                "title": v.title,
+                "status": v.status,
+                "updated_at": v.updated_at,
+                "recorded_at": v.recorded_at,
+                "status_change": status_change,
+                "changes": prev.map(|p| json_diff(&p.raw, &v.raw)).unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    let diff = match (q.from, q.to) {
+        (Some(from), Some(to)) => {
+            let find = |n: i32| {
+                versions.iter().find(|v| v.version == n).ok_or_else(|| {
+                    ApiError::new_not_found(format!("dataset {} has no version {}", dataset_id, n))
+                })
+            };
+            let (a, b) = (find(from)?, find(to)?);
+            Some(serde_json::json!({ "from": from, "to": to, "changes": json_diff(&a.raw, &b.raw) }))
+        }
+        (None, None) => None,
+        _ => return Err(ApiError::new_bad_request("`from` and `to` must be given together".to_string())),
+    };
+
+    Ok(Json(serde_json::json!({
+        "dataset_id": dataset_id,
+        "versions": items,
+        "diff": diff,
+    })))
+}
+
+/// Resolves 1-based `page` and `per_page` and the row offset, capping both so the offset
+/// cannot overflow.
+fn paging(page: Option<i64>, per_page: Option<i64>, default_per_page: i64) -> (i64, i64, i64) {
+    let page = page.unwrap_or(1).clamp(1, LIST_MAX_PAGE);
+    let per_page = per_page.unwrap_or(default_per_page).clamp(1, LIST_MAX_PER_PAGE);
+    (page, per_page, (page - 1) * per_page)
+}
@@ -0,0 +1,104 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{ws::WebSocketUpgrade, Path, Query, State},
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use serde_json::Value;
+use tokio_stream::Stream;
+use tracing::info;
+
+use crate::domain::error::ApiError;
+use crate::domain::models::{AppState, IssHistoryQuery, IssPositionResponse, IssTrendQuery, Trend};
+use crate::handlers::iss::{
+    history_for, last_for, last_position_for, stream_for, trend_for, trigger_for, ws_for,
+};
+
+/// Lists the NORAD IDs of all tracked satellites.
+pub async fn list_satellites(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    Ok(Json(serde_json::json!({ "satellites": state.iss_service.tracked_satellites() })))
+}
+
+/// Gets the most recent log of a tracked satellite.
+pub async fn satellite_last(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    info!("Received request for last position of satellite {}", id);
+    ensure_tracked(&state, id)?;
+    last_for(&state, id).await
+}
+
+/// Gets the most recent validated position of a tracked satellite in the stable v2 shape.
+pub async fn satellite_last_v2(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<IssPositionResponse>, ApiError> {
+    info!("Received request for last position of satellite {} (v2)", id);
+    ensure_tracked(&state, id)?;
+    last_position_for(&state, id).await
+}
+
+/// Triggers a new fetch for a tracked satellite and returns the latest log.
+pub async fn satellite_fetch(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    ensure_tracked(&state, id)?;
+    trigger_for(&state, id).await
+}
+
+/// Calculates the movement trend of a tracked satellite.
+pub async fn satellite_trend(
+    Path(id): Path<i64>,
+    Query(q): Query<IssTrendQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Trend>, ApiError> {
+    info!("Received request for trend of satellite {}", id);
+    ensure_tracked(&state, id)?;
+    trend_for(&state, id, &q).await
+}
+
+/// Returns the track of a tracked satellite for a time range.
+pub async fn satellite_history(
+    Path(id): Path<i64>,
+    Query(q): Query<IssHistoryQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    info!("Received request for history of satellite {}", id);
+    ensure_tracked(&state, id)?;
+    history_for(&state, id, &q).await
+}
+
+/// Streams newly stored rows of a tracked satellite over SSE.
+pub async fn satellite_stream(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    ensure_tracked(&state, id)?;
+    Ok(stream_for(&state, id))
+}
+
+/// Streams newly stored rows of a tracked satellite over a WebSocket.
+pub async fn satellite_ws(
+    Path(id): Path<i64>,
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> Response {
+    match ensure_tracked(&state, id) {
+        Ok(()) => ws_for(ws, &state, id),
+        Err(e) => e.into_response(),
+    }
+}
+
+fn ensure_tracked(state: &AppState, id: i64) -> Result<(), ApiError> {
+    if state.iss_service.is_tracked(id) {
+        Ok(())
+    } else {
+        Err(ApiError::new_not_found(format!("satellite {} is not tracked", id)))
+    }
+}
@@ -4,18 +4,54 @@ use axum::{
     Json,
 };
 use serde_json::{json, Value};
-use crate::domain::models::AppState;
+use crate::domain::models::{AppState, SpaceHistoryQuery, TerminatorQuery, ISS_NORAD_ID};
 use crate::domain::error::ApiError;
+use crate::domain::solar::terminator_geojson;
+use crate::services::space_service::SPACE_SOURCES;
+
+const HISTORY_DEFAULT_DAYS: i64 = 7;
+const HISTORY_DEFAULT_LIMIT: i64 = 100;
+const HISTORY_MAX_LIMIT: i64 = 1000;
 
 /// Handler to get the latest cached data for a specific source.
 pub async fn space_latest(
     Path(src): Path<String>,
     State(state): State<AppState>,
 ) -> Result<Json<Value>, ApiError> {
-    match state.cache_repo.get_latest(&src) {
-        Ok(value) => Ok(Json(value)),
-        Err(_) => Ok(Json(json!({ "source": src, "message": "no data found in cache" }))),
+    match state.space_service.latest(&src).await {
+        Some(value) => Ok(Json(value)),
+        None => Ok(Json(json!({ "source": src, "message": "no data found in cache" }))),
+    }
+}
+
+/// Handler to list the stored fetches of a source between `from` and `to` (default: the last week).
+pub async fn space_history(
+    Path(src): Path<String>,
+    Query(q): Query<SpaceHistoryQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    if !SPACE_SOURCES.contains(&src.as_str()) {
+        return Err(ApiError::new_bad_request(format!(
+            "unknown source `{}`, expected one of {}",
+            src,
+            SPACE_SOURCES.join(", ")
+        )));
     }
+    let to = q.to.unwrap_or_else(chrono::Utc::now);
+    let from = q.from.unwrap_or(to - chrono::Duration::days(HISTORY_DEFAULT_DAYS));
+    if from > to {
+        return Err(ApiError::new_bad_request("`from` must not be after `to`".to_string()));
+    }
+    let limit = q.limit.unwrap_or(HISTORY_DEFAULT_LIMIT).clamp(1, HISTORY_MAX_LIMIT);
+
+    let items = state.space_service.history(&src, from, to, limit).await.map_err(ApiError::from)?;
+    Ok(Json(json!({
+        "source": src,
+        "from": from,
+        "to": to,
+        "count": items.len(),
+        "items": items,
+    })))
 }
 
 /// Handler to trigger a refresh of cached data for specified sources.
@@ -49,13 +85,14 @@ pub async fn space_refresh(
 pub async fn space_summary(
     State(state): State<AppState>,
 ) -> Result<Json<Value>, ApiError> {
-    let apod_val = state.cache_repo.get_latest("apod").unwrap_or_default();
-    let neo_val = state.cache_repo.get_latest("neo").unwrap_or_default();
-    let flr_val = state.cache_repo.get_latest("flr").unwrap_or_default();
-    let cme_val = state.cache_repo.get_latest("cme").unwrap_or_default();
-    let spacex_val = state.cache_repo.get_latest("spacex").unwrap_or_default();
+    let space = &state.space_service;
+    let apod_val = space.latest("apod").await.unwrap_or_default();
+    let neo_val = space.latest("neo").await.unwrap_or_default();
+    let flr_val = space.latest("flr").await.unwrap_or_default();
+    let cme_val = space.latest("cme").await.unwrap_or_default();
+    let spacex_val = space.latest("spacex").await.unwrap_or_default();
 
-    let iss_val = state.iss_repo.get_last().await.map_err(ApiError::from)?;
+    let iss_val = state.iss_repo.get_last(ISS_NORAD_ID).await.map_err(ApiError::from)?;
     let osdr_count = state.osdr_repo.count().await.map_err(ApiError::from)?;
 
     Ok(Json(json!({
@@ -68,3 +105,12 @@ pub async fn space_summary(
         "osdr_count": osdr_count,
     })))
 }
+
+/// Handler to get the day/night terminator at `at` (defaults to now) as a GeoJSON night-side polygon.
+pub async fn space_terminator(
+    Query(q): Query<TerminatorQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let at = q.at.unwrap_or_else(chrono::Utc::now);
+    let step = q.step.unwrap_or(2.0).clamp(0.5, 10.0);
+    Ok(Json(terminator_geojson(at, step)))
+}
@@ -1,7 +1,7 @@
 use sqlx::postgres::PgPoolOptions;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 mod config;
@@ -31,6 +31,13 @@ async fn main() -> anyhow::Result<()> {
     // Create shared application state
     let state = config::new(pool.clone()).await;
 
+    // Restore Redis keys lost in a flush from the stored fetch history
+    if let Err(e) = state.space_service.warm_cache().await {
+        warn!("Could not warm the space cache: {:?}", e);
+    }
+
+    state.osdr_service.log_mapping_source().await;
+
     // Spawn all background jobs
     let job_service = Arc::new(state.job_service.clone());
     job_service.spawn_all_jobs();
@@ -1,16 +1,140 @@
 use anyhow::Result;
-use sqlx::PgPool;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use sqlx::{Connection, PgConnection, PgPool};
+use tracing::info;
+
+/// Monthly `iss_fetch_log` partitions kept ready beyond the current month.
+pub const ISS_PARTITION_MONTHS_AHEAD: u32 = 3;
+
+/// `iss_fetch_log` is range-partitioned by month on `fetched_at`, so the primary key has to include it.
+const CREATE_ISS_FETCH_LOG: &str = "CREATE TABLE IF NOT EXISTS iss_fetch_log(
+    id BIGINT NOT NULL DEFAULT nextval('iss_fetch_log_id_seq'),
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    source_url TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    satellite_id BIGINT NOT NULL DEFAULT 25544,
+    quality TEXT NOT NULL DEFAULT 'ok',
+    lat DOUBLE PRECISION,
+    lon DOUBLE PRECISION,
+    altitude DOUBLE PRECISION,
+    velocity DOUBLE PRECISION,
+    visibility TEXT,
+    footprint DOUBLE PRECISION,
+    PRIMARY KEY (id, fetched_at)
+) PARTITION BY RANGE (fetched_at)";
 
 pub async fn init_db(pool: &PgPool) -> Result<()> {
     // ISS
+    // Decided before any DDL below adds the typed columns.
+    let backfill_typed: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM information_schema.tables WHERE table_name = 'iss_fetch_log')
+            AND NOT EXISTS (SELECT 1 FROM information_schema.columns
+                            WHERE table_name = 'iss_fetch_log' AND column_name = 'lat')"
+    ).fetch_one(pool).await?;
+    sqlx::query("CREATE SEQUENCE IF NOT EXISTS iss_fetch_log_id_seq").execute(pool).await?;
+    sqlx::query(CREATE_ISS_FETCH_LOG).execute(pool).await?;
+    // Rows written before multi-satellite tracking all belong to the ISS.
+    sqlx::query("ALTER TABLE iss_fetch_log ADD COLUMN IF NOT EXISTS satellite_id BIGINT NOT NULL DEFAULT 25544").execute(pool).await?;
+    sqlx::query("ALTER TABLE iss_fetch_log ADD COLUMN IF NOT EXISTS quality TEXT NOT NULL DEFAULT 'ok'").execute(pool).await?;
+    sqlx::query(
+        "ALTER TABLE iss_fetch_log
+            ADD COLUMN IF NOT EXISTS lat DOUBLE PRECISION,
+            ADD COLUMN IF NOT EXISTS lon DOUBLE PRECISION,
+            ADD COLUMN IF NOT EXISTS altitude DOUBLE PRECISION,
+            ADD COLUMN IF NOT EXISTS velocity DOUBLE PRECISION,
+            ADD COLUMN IF NOT EXISTS visibility TEXT,
+            ADD COLUMN IF NOT EXISTS footprint DOUBLE PRECISION"
+    ).execute(pool).await?;
+    migrate_iss_fetch_log_to_partitions(pool).await?;
+    // One-time backfill of rows stored before the typed columns existed. Only numeric,
+    // in-range coordinates are copied, mirroring what `IssPosition::parse` accepts.
+    if backfill_typed {
+        sqlx::query(
+            "UPDATE iss_fetch_log SET
+                lat = (payload->>'latitude')::float8,
+                lon = (payload->>'longitude')::float8,
+                altitude = CASE WHEN jsonb_typeof(payload->'altitude') = 'number'
+                                THEN (payload->>'altitude')::float8 END,
+                velocity = CASE WHEN jsonb_typeof(payload->'velocity') = 'number'
+                                THEN (payload->>'velocity')::float8 END,
+                visibility = CASE WHEN jsonb_typeof(payload->'visibility') = 'string'
+                                  THEN payload->>'visibility' END,
+                footprint = CASE WHEN jsonb_typeof(payload->'footprint') = 'number'
+                                 THEN (payload->>'footprint')::float8 END
+             WHERE lat IS NULL AND quality = 'ok'
+               AND CASE WHEN jsonb_typeof(payload->'latitude') = 'number'
+                        THEN (payload->>'latitude')::float8 BETWEEN -90 AND 90 ELSE false END
+               AND CASE WHEN jsonb_typeof(payload->'longitude') = 'number'
+                        THEN (payload->>'longitude')::float8 BETWEEN -180 AND 180 ELSE false END"
+        ).execute(pool).await?;
+        // Whatever could not be backfilled has no usable position.
+        sqlx::query("UPDATE iss_fetch_log SET quality = 'invalid' WHERE lat IS NULL AND quality = 'ok'")
+            .execute(pool)
+            .await?;
+    }
+    ensure_iss_partitions(&mut *pool.acquire().await?, Utc::now(), ISS_PARTITION_MONTHS_AHEAD).await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS ix_iss_fetch_log_fetched_at ON iss_fetch_log(fetched_at DESC)").execute(pool).await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS ix_iss_fetch_log_satellite ON iss_fetch_log(satellite_id, fetched_at DESC)").execute(pool).await?;
+
+    // Downsampled positions kept after the raw rows fall out of retention
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS iss_fetch_log(
+        "CREATE TABLE IF NOT EXISTS iss_fetch_log_rollup(
+            satellite_id BIGINT NOT NULL,
+            bucket_start TIMESTAMPTZ NOT NULL,
+            lat DOUBLE PRECISION NOT NULL,
+            lon DOUBLE PRECISION NOT NULL,
+            altitude DOUBLE PRECISION,
+            velocity DOUBLE PRECISION,
+            samples INT NOT NULL,
+            PRIMARY KEY (satellite_id, bucket_start)
+        )"
+    ).execute(pool).await?;
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS iss_retention_runs(
             id BIGSERIAL PRIMARY KEY,
-            fetched_at TIMESTAMPTZ NOT NULL DEFAULT now(),
-            source_url TEXT NOT NULL,
-            payload JSONB NOT NULL
+            ran_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            cutoff TIMESTAMPTZ NOT NULL,
+            rolled_up BIGINT NOT NULL,
+            deleted BIGINT NOT NULL
+        )"
+    ).execute(pool).await?;
+
+    // Geofences and the enter/exit events recorded against them
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS geofences(
+            id BIGSERIAL PRIMARY KEY,
+            name TEXT NOT NULL,
+            shape JSONB NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        )"
+    ).execute(pool).await?;
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS geofence_events(
+            id BIGSERIAL PRIMARY KEY,
+            geofence_id BIGINT NOT NULL REFERENCES geofences(id) ON DELETE CASCADE,
+            satellite_id BIGINT NOT NULL,
+            event TEXT NOT NULL,
+            lat DOUBLE PRECISION NOT NULL,
+            lon DOUBLE PRECISION NOT NULL,
+            occurred_at TIMESTAMPTZ NOT NULL
         )"
     ).execute(pool).await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS ix_geofence_events_satellite ON geofence_events(satellite_id, occurred_at DESC)").execute(pool).await?;
+
+    // TLE element sets for orbit propagation
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS tle_sets(
+            id BIGSERIAL PRIMARY KEY,
+            norad_id BIGINT NOT NULL,
+            name TEXT,
+            line1 TEXT NOT NULL,
+            line2 TEXT NOT NULL,
+            epoch TIMESTAMPTZ NOT NULL,
+            source TEXT NOT NULL,
+            fetched_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        )"
+    ).execute(pool).await?;
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS ux_tle_sets_norad_epoch ON tle_sets(norad_id, epoch)").execute(pool).await?;
 
     // OSDR
     sqlx::query(
@@ -28,6 +152,105 @@ pub async fn init_db(pool: &PgPool) -> Result<()> {
         "CREATE UNIQUE INDEX IF NOT EXISTS ux_osdr_dataset_id
          ON osdr_items(dataset_id) WHERE dataset_id IS NOT NULL"
     ).execute(pool).await?;
+    sqlx::query("ALTER TABLE osdr_items ADD COLUMN IF NOT EXISTS content_hash TEXT").execute(pool).await?;
+    sqlx::query("UPDATE osdr_items SET content_hash = md5(raw::text) WHERE content_hash IS NULL").execute(pool).await?;
+    // Presence in the upstream listing; items missing from several complete syncs are withdrawn.
+    sqlx::query(
+        "ALTER TABLE osdr_items
+            ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMPTZ,
+            ADD COLUMN IF NOT EXISTS missed_syncs INT NOT NULL DEFAULT 0,
+            ADD COLUMN IF NOT EXISTS withdrawn_at TIMESTAMPTZ"
+    ).execute(pool).await?;
+    sqlx::query("UPDATE osdr_items SET last_seen_at = inserted_at WHERE last_seen_at IS NULL").execute(pool).await?;
+    // Incremental sync position per upstream URL
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS osdr_sync_state(
+            source TEXT PRIMARY KEY,
+            watermark TIMESTAMPTZ,
+            last_synced_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        )"
+    ).execute(pool).await?;
+    // Full-text search document: title first, then the descriptive fields of the raw record.
+    sqlx::query(
+        "ALTER TABLE osdr_items ADD COLUMN IF NOT EXISTS search_tsv tsvector
+         GENERATED ALWAYS AS (
+            setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+            setweight(to_tsvector('english', coalesce(dataset_id, '')), 'A') ||
+            setweight(to_tsvector('english',
+                coalesce(raw->>'description', '') || ' ' ||
+                coalesce(raw->>'summary', '') || ' ' ||
+                coalesce(raw->>'abstract', '')), 'B') ||
+            setweight(to_tsvector('english',
+                coalesce(raw->>'keywords', '') || ' ' ||
+                coalesce(raw->>'organism', '') || ' ' ||
+                coalesce(raw->>'project_type', '')), 'C')
+         ) STORED"
+    ).execute(pool).await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS ix_osdr_items_search ON osdr_items USING GIN (search_tsv)").execute(pool).await?;
+
+    // Every distinct content of a dataset, numbered per dataset
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS osdr_item_versions(
+            id BIGSERIAL PRIMARY KEY,
+            dataset_id TEXT NOT NULL,
+            version INT NOT NULL,
+            content_hash TEXT NOT NULL,
+            title TEXT,
+            status TEXT,
+            updated_at TIMESTAMPTZ,
+            raw JSONB NOT NULL,
+            recorded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            UNIQUE (dataset_id, version)
+        )"
+    ).execute(pool).await?;
+    // Items synced before versioning start their history at version 1.
+    sqlx::query(
+        "INSERT INTO osdr_item_versions (dataset_id, version, content_hash, title, status, updated_at, raw, recorded_at)
+         SELECT dataset_id, 1, md5(raw::text), title, status, updated_at, raw, inserted_at
+         FROM osdr_items i
+         WHERE dataset_id IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM osdr_item_versions v WHERE v.dataset_id = i.dataset_id)"
+    ).execute(pool).await?;
+    // Audit log of OSDR sync runs; a run still `running` at startup was cut off by a restart.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS osdr_sync_runs(
+            id BIGSERIAL PRIMARY KEY,
+            full_sync BOOLEAN NOT NULL,
+            status TEXT NOT NULL DEFAULT 'running',
+            started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            finished_at TIMESTAMPTZ,
+            pages INT NOT NULL DEFAULT 0,
+            fetched INT NOT NULL DEFAULT 0,
+            upserted INT NOT NULL DEFAULT 0,
+            unchanged INT NOT NULL DEFAULT 0,
+            skipped INT NOT NULL DEFAULT 0,
+            invalid INT NOT NULL DEFAULT 0,
+            withdrawn INT NOT NULL DEFAULT 0,
+            error TEXT
+        )"
+    ).execute(pool).await?;
+    sqlx::query(
+        "UPDATE osdr_sync_runs SET status = 'interrupted', finished_at = now() WHERE status = 'running'"
+    ).execute(pool).await?;
+    // At most one run is in progress at a time.
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS ux_osdr_sync_runs_running ON osdr_sync_runs ((true)) WHERE status = 'running'"
+    ).execute(pool).await?;
+    // Quarantine for upstream OSDR items that failed validation, one row per distinct payload
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS osdr_rejected_items(
+            id BIGSERIAL PRIMARY KEY,
+            source TEXT NOT NULL,
+            content_hash TEXT NOT NULL UNIQUE,
+            dataset_id TEXT,
+            raw JSONB NOT NULL,
+            errors JSONB NOT NULL,
+            run_id BIGINT,
+            first_rejected_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            last_rejected_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            times_rejected INT NOT NULL DEFAULT 1
+        )"
+    ).execute(pool).await?;
 
     // универсальный кэш космоданных
     sqlx::query(
@@ -42,3 +265,150 @@ pub async fn init_db(pool: &PgPool) -> Result<()> {
 
     Ok(())
 }
+
+/// Creates the monthly `iss_fetch_log` partitions from the month of `from` through
+/// `months_ahead` months after it, plus the default partition that catches rows outside
+/// them. Existing partitions are left alone. Rows of a new month already sitting in the
+/// default partition are moved into it.
+pub async fn ensure_iss_partitions(conn: &mut PgConnection, from: DateTime<Utc>, months_ahead: u32) -> Result<()> {
+    sqlx::query("CREATE TABLE IF NOT EXISTS iss_fetch_log_default PARTITION OF iss_fetch_log DEFAULT")
+        .execute(&mut *conn)
+        .await?;
+    let mut start = NaiveDate::from_ymd_opt(from.year(), from.month(), 1).expect("first day of month is valid");
+    for _ in 0..=months_ahead {
+        let end = next_month(start);
+        let name = format!("iss_fetch_log_p{}_{:02}", start.year(), start.month());
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+            .bind(&name)
+            .fetch_one(&mut *conn)
+            .await?;
+        if !exists {
+            let bounds = format!("FROM ('{} 00:00:00+00') TO ('{} 00:00:00+00')", start, end);
+            let (lower, upper) = (month_start(start), month_start(end));
+            let in_default: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM iss_fetch_log_default WHERE fetched_at >= $1 AND fetched_at < $2)"
+            )
+                .bind(lower)
+                .bind(upper)
+                .fetch_one(&mut *conn)
+                .await?;
+            if in_default {
+                // A range partition cannot be created over rows of the default one, so build
+                // it standalone, move the rows over and attach it.
+                let mut tx = conn.begin().await?;
+                sqlx::query(&format!("CREATE TABLE {} (LIKE iss_fetch_log INCLUDING DEFAULTS INCLUDING CONSTRAINTS)", name))
+                    .execute(&mut *tx)
+                    .await?;
+                let moved = sqlx::query(&format!(
+                    "WITH moved AS (
+                        DELETE FROM iss_fetch_log_default WHERE fetched_at >= $1 AND fetched_at < $2 RETURNING *
+                     )
+                     INSERT INTO {} SELECT * FROM moved",
+                    name
+                ))
+                    .bind(lower)
+                    .bind(upper)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected();
+                sqlx::query(&format!("ALTER TABLE iss_fetch_log ATTACH PARTITION {} FOR VALUES {}", name, bounds))
+                    .execute(&mut *tx)
+                    .await?;
+                tx.commit().await?;
+                info!("Moved {} rows from the default partition into {}.", moved, name);
+            } else {
+                sqlx::query(&format!("CREATE TABLE IF NOT EXISTS {} PARTITION OF iss_fetch_log FOR VALUES {}", name, bounds))
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+        start = end;
+    }
+    Ok(())
+}
+
+/// Drops the monthly `iss_fetch_log` partitions that end at or before `cutoff` and returns
+/// how many rows they held. Rows older than `cutoff` in other partitions are left alone.
+pub async fn drop_iss_partitions_before(conn: &mut PgConnection, cutoff: DateTime<Utc>) -> Result<u64> {
+    let names: Vec<String> = sqlx::query_scalar(
+        "SELECT c.relname::text FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid
+         WHERE i.inhparent = 'iss_fetch_log'::regclass AND c.relname ~ '^iss_fetch_log_p[0-9]{4}_[0-9]{2}$'"
+    )
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut dropped = 0;
+    for name in names {
+        let Ok(start) = NaiveDate::parse_from_str(&format!("{}_01", &name["iss_fetch_log_p".len()..]), "%Y_%m_%d") else {
+            continue;
+        };
+        if month_start(next_month(start)) > cutoff {
+            continue;
+        }
+        // Older partitions were bounded in the session time zone; never drop one with newer rows.
+        let newer: bool = sqlx::query_scalar(&format!("SELECT EXISTS (SELECT 1 FROM {} WHERE fetched_at >= $1)", name))
+            .bind(cutoff)
+            .fetch_one(&mut *conn)
+            .await?;
+        if newer {
+            continue;
+        }
+        let rows: i64 = sqlx::query_scalar(&format!("SELECT count(*) FROM {}", name))
+            .fetch_one(&mut *conn)
+            .await?;
+        sqlx::query(&format!("DROP TABLE {}", name)).execute(&mut *conn).await?;
+        info!("Dropped expired partition {} ({} rows).", name, rows);
+        dropped += rows as u64;
+    }
+    Ok(dropped)
+}
+
+/// Moves rows of a pre-partitioning `iss_fetch_log` into the partitioned table, keeping ids.
+/// Does nothing once the table is partitioned.
+async fn migrate_iss_fetch_log_to_partitions(pool: &PgPool) -> Result<()> {
+    let kind: Option<i8> = sqlx::query_scalar("SELECT relkind::\"char\" FROM pg_class WHERE oid = to_regclass('iss_fetch_log')")
+        .fetch_optional(pool)
+        .await?;
+    if kind != Some(b'r' as i8) {
+        return Ok(());
+    }
+    info!("Migrating iss_fetch_log to monthly partitions...");
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("ALTER TABLE iss_fetch_log RENAME TO iss_fetch_log_legacy").execute(&mut *tx).await?;
+    sqlx::query("ALTER TABLE iss_fetch_log_legacy RENAME CONSTRAINT iss_fetch_log_pkey TO iss_fetch_log_legacy_pkey").execute(&mut *tx).await?;
+    sqlx::query("DROP INDEX IF EXISTS ix_iss_fetch_log_fetched_at").execute(&mut *tx).await?;
+    sqlx::query("DROP INDEX IF EXISTS ix_iss_fetch_log_satellite").execute(&mut *tx).await?;
+    sqlx::query(CREATE_ISS_FETCH_LOG).execute(&mut *tx).await?;
+    // Keep the id sequence alive when the legacy table that owned it is dropped.
+    sqlx::query("ALTER SEQUENCE iss_fetch_log_id_seq OWNED BY iss_fetch_log.id").execute(&mut *tx).await?;
+
+    let oldest: Option<DateTime<Utc>> = sqlx::query_scalar("SELECT min(fetched_at) FROM iss_fetch_log_legacy")
+        .fetch_one(&mut *tx)
+        .await?;
+    let now = Utc::now();
+    let from = oldest.unwrap_or(now).min(now);
+    let months = (now.year() - from.year()) * 12 + now.month() as i32 - from.month() as i32;
+    ensure_iss_partitions(&mut tx, from, months as u32 + ISS_PARTITION_MONTHS_AHEAD).await?;
+
+    let moved = sqlx::query(
+        "INSERT INTO iss_fetch_log
+            (id, fetched_at, source_url, payload, satellite_id, quality, lat, lon, altitude, velocity, visibility, footprint)
+         SELECT id, fetched_at, source_url, payload, satellite_id, quality, lat, lon, altitude, velocity, visibility, footprint
+         FROM iss_fetch_log_legacy"
+    ).execute(&mut *tx).await?.rows_affected();
+    sqlx::query("DROP TABLE iss_fetch_log_legacy").execute(&mut *tx).await?;
+    tx.commit().await?;
+
+    info!("Moved {} rows into the partitioned iss_fetch_log.", moved);
+    Ok(())
+}
+
+fn month_start(d: NaiveDate) -> DateTime<Utc> {
+    d.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc()
+}
+
+fn next_month(d: NaiveDate) -> NaiveDate {
+    let (y, m) = if d.month() == 12 { (d.year() + 1, 1) } else { (d.year(), d.month() + 1) };
+    NaiveDate::from_ymd_opt(y, m, 1).expect("first day of month is valid")
+}
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use crate::domain::models::{Geofence, GeofenceEvent, GeofenceShape};
+
+/// Repository for managing geofences and their events in the database.
+#[derive(Clone)]
+pub struct GeofenceRepo {
+    pool: PgPool,
+}
+
+impl GeofenceRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Creates a new geofence and returns it.
+    pub async fn create(&self, name: &str, shape: &GeofenceShape) -> Result<Geofence> {
+        let geofence: Geofence = sqlx::query_as(
+            "INSERT INTO geofences(name, shape) VALUES($1, $2) RETURNING *"
+        )
+        .bind(name)
+        .bind(Json(shape))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(geofence)
+    }
+
+    /// Lists all geofences.
+    pub async fn list(&self) -> Result<Vec<Geofence>> {
+        let geofences: Vec<Geofence> = sqlx::query_as("SELECT * FROM geofences ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(geofences)
+    }
+
+    /// Deletes a geofence and its events. Returns `false` if it did not exist.
+    pub async fn delete(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM geofences WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records an enter/exit event.
+    pub async fn create_event(
+        &self,
+        geofence_id: i64,
+        satellite_id: i64,
+        event: &str,
+        lat: f64,
+        lon: f64,
+        occurred_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO geofence_events(geofence_id, satellite_id, event, lat, lon, occurred_at)
+             VALUES($1, $2, $3, $4, $5, $6)"
+        )
+        .bind(geofence_id)
+        .bind(satellite_id)
+        .bind(event)
+        .bind(lat)
+        .bind(lon)
+        .bind(occurred_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Lists events of a satellite within `[from, to]`, newest first.
+    pub async fn list_events(
+        &self,
+        satellite_id: i64,
+        geofence_id: Option<i64>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<GeofenceEvent>> {
+        let events: Vec<GeofenceEvent> = sqlx::query_as(
+            "SELECT e.id, e.geofence_id, g.name AS geofence_name, e.satellite_id, e.event,
+                    e.lat, e.lon, e.occurred_at
+             FROM geofence_events e
+             JOIN geofences g ON g.id = e.geofence_id
+             WHERE e.satellite_id = $1
+               AND ($2::BIGINT IS NULL OR e.geofence_id = $2)
+               AND e.occurred_at BETWEEN $3 AND $4
+             ORDER BY e.occurred_at DESC
+             LIMIT $5"
+        )
+        .bind(satellite_id)
+        .bind(geofence_id)
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(events)
+    }
+}
@@ -1,7 +1,9 @@
 use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
 use serde_json::Value;
 use sqlx::PgPool;
-use crate::domain::models::IssFetchLog;
+use crate::domain::models::{IssFetchLog, IssHistoryPoint, IssPosition, RetentionRun};
+use crate::repo::db::{drop_iss_partitions_before, ensure_iss_partitions};
 
 /// Repository for managing ISS fetch logs in the database.
 #[derive(Clone)]
@@ -14,29 +16,195 @@ impl IssRepo {
         Self { pool }
     }
 
-    /// Creates a new log entry for an ISS fetch.
-    pub async fn create_log(&self, url: &str, payload: &Value) -> Result<()> {
-        sqlx::query("INSERT INTO iss_fetch_log (source_url, payload) VALUES ($1, $2)")
+    /// Creates a new log entry for a satellite fetch and returns the stored row.
+    /// The typed columns stay `NULL` when no validated `position` is given.
+    pub async fn create_log(
+        &self,
+        satellite_id: i64,
+        url: &str,
+        payload: &Value,
+        position: Option<&IssPosition>,
+        quality: &str,
+    ) -> Result<IssFetchLog> {
+        let log: IssFetchLog = sqlx::query_as(
+            "INSERT INTO iss_fetch_log
+                (satellite_id, source_url, payload, quality, lat, lon, altitude, velocity, visibility, footprint)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING *"
+        )
+            .bind(satellite_id)
             .bind(url)
             .bind(payload)
-            .execute(&self.pool)
+            .bind(quality)
+            .bind(position.map(|p| p.latitude))
+            .bind(position.map(|p| p.longitude))
+            .bind(position.and_then(|p| p.altitude))
+            .bind(position.and_then(|p| p.velocity))
+            .bind(position.and_then(|p| p.visibility.clone()))
+            .bind(position.and_then(|p| p.footprint))
+            .fetch_one(&self.pool)
             .await?;
-        Ok(())
+        Ok(log)
     }
 
-    /// Gets the most recent ISS log entry.
-    pub async fn get_last(&self) -> Result<Option<Value>> {
-        let result: Option<IssFetchLog> = sqlx::query_as("SELECT * FROM iss_fetch_log ORDER BY id DESC LIMIT 1")
+    /// Gets the most recent log entry for a satellite.
+    pub async fn get_last(&self, satellite_id: i64) -> Result<Option<Value>> {
+        let result: Option<IssFetchLog> = sqlx::query_as("SELECT * FROM iss_fetch_log WHERE satellite_id = $1 ORDER BY fetched_at DESC, id DESC LIMIT 1")
+            .bind(satellite_id)
             .fetch_optional(&self.pool)
             .await?;
         Ok(result.map(|log| serde_json::to_value(log).unwrap_or_default()))
     }
 
-    /// Gets the last two ISS log entries for trend calculation.
-    pub async fn get_last_two(&self) -> Result<Vec<IssFetchLog>> {
-        let logs: Vec<IssFetchLog> = sqlx::query_as("SELECT * FROM iss_fetch_log ORDER BY id DESC LIMIT 2")
+    /// Gets the most recent valid log entry of a satellite that has typed coordinates.
+    pub async fn get_last_position(&self, satellite_id: i64) -> Result<Option<IssFetchLog>> {
+        let result: Option<IssFetchLog> = sqlx::query_as(
+            "SELECT * FROM iss_fetch_log
+             WHERE satellite_id = $1 AND quality = 'ok' AND lat IS NOT NULL AND lon IS NOT NULL
+             ORDER BY fetched_at DESC, id DESC LIMIT 1"
+        )
+            .bind(satellite_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(result)
+    }
+
+    /// Gets the most recent log entry of a satellite that was not flagged as anomalous.
+    pub async fn get_last_good(&self, satellite_id: i64) -> Result<Option<IssFetchLog>> {
+        let result: Option<IssFetchLog> = sqlx::query_as(
+            "SELECT * FROM iss_fetch_log WHERE satellite_id = $1 AND quality = 'ok' ORDER BY fetched_at DESC, id DESC LIMIT 1"
+        )
+            .bind(satellite_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(result)
+    }
+
+    /// Gets positions of a satellite fetched within `[from, to]`, oldest first.
+    /// Past the retention cutoff the raw rows are gone, so the downsampled rollup points
+    /// (one per bucket, quality `ok`) are returned there instead.
+    /// When the range holds more than `limit` rows, the most recent ones are kept.
+    pub async fn get_range(
+        &self,
+        satellite_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+        exclude_flagged: bool,
+    ) -> Result<Vec<IssHistoryPoint>> {
+        let points: Vec<IssHistoryPoint> = sqlx::query_as(
+            "SELECT lat, lon, altitude, velocity, fetched_at, quality FROM (
+                SELECT lat, lon, altitude, velocity, fetched_at, quality FROM (
+                    SELECT lat, lon, altitude, velocity, fetched_at, quality
+                    FROM iss_fetch_log
+                    WHERE satellite_id = $1
+                      AND fetched_at BETWEEN $2 AND $3
+                      AND lat IS NOT NULL AND lon IS NOT NULL
+                      AND (NOT $5 OR quality = 'ok')
+                    UNION ALL
+                    SELECT lat, lon, altitude, velocity, bucket_start, 'ok'
+                    FROM iss_fetch_log_rollup
+                    WHERE satellite_id = $1
+                      AND bucket_start BETWEEN $2 AND $3
+                ) u
+                ORDER BY fetched_at DESC
+                LIMIT $4
+             ) t
+             ORDER BY fetched_at ASC"
+        )
+        .bind(satellite_id)
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .bind(exclude_flagged)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(points)
+    }
+
+    /// Downsamples good rows fetched before `cutoff` into `iss_fetch_log_rollup`
+    /// (the first sample of every `bucket_minutes` bucket), removes all raw rows before
+    /// `cutoff`, dropping monthly partitions that lie entirely before it, and records the run,
+    /// all in one transaction. `cutoff` is floored to a bucket
+    /// boundary so no bucket is rolled up before all of its rows are due.
+    pub async fn prune(&self, cutoff: DateTime<Utc>, bucket_minutes: i64) -> Result<RetentionRun> {
+        let bucket_secs = bucket_minutes.max(1) * 60;
+        let cutoff = Utc
+            .timestamp_opt(cutoff.timestamp().div_euclid(bucket_secs) * bucket_secs, 0)
+            .single()
+            .unwrap_or(cutoff);
+        let mut tx = self.pool.begin().await?;
+
+        let rolled_up = sqlx::query(
+            "INSERT INTO iss_fetch_log_rollup (satellite_id, bucket_start, lat, lon, altitude, velocity, samples)
+             SELECT DISTINCT ON (satellite_id, bucket_start)
+                    satellite_id, bucket_start, lat, lon, altitude, velocity,
+                    count(*) OVER (PARTITION BY satellite_id, bucket_start)
+             FROM (
+                SELECT satellite_id,
+                       to_timestamp(floor(extract(epoch FROM fetched_at) / $2) * $2) AS bucket_start,
+                       lat, lon, altitude, velocity, fetched_at
+                FROM iss_fetch_log
+                WHERE fetched_at < $1
+                  AND quality = 'ok'
+                  AND lat IS NOT NULL AND lon IS NOT NULL
+             ) t
+             ORDER BY satellite_id, bucket_start, fetched_at
+             ON CONFLICT (satellite_id, bucket_start) DO NOTHING"
+        )
+        .bind(cutoff)
+        .bind(bucket_secs as f64)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // Whole expired months go at once; only the partition straddling `cutoff` and the
+        // default one need row deletes.
+        let dropped = drop_iss_partitions_before(&mut tx, cutoff).await?;
+        let deleted = dropped + sqlx::query("DELETE FROM iss_fetch_log WHERE fetched_at < $1")
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        let run: RetentionRun = sqlx::query_as(
+            "INSERT INTO iss_retention_runs (cutoff, rolled_up, deleted) VALUES ($1, $2, $3) RETURNING *"
+        )
+        .bind(cutoff)
+        .bind(rolled_up as i64)
+        .bind(deleted as i64)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(run)
+    }
+
+    /// Creates the `iss_fetch_log` partitions for this month and `months_ahead` months after it.
+    pub async fn ensure_partitions(&self, months_ahead: u32) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        ensure_iss_partitions(&mut conn, Utc::now(), months_ahead).await
+    }
+
+    /// Gets the most recent retention runs, newest first.
+    pub async fn list_retention_runs(&self, limit: i64) -> Result<Vec<RetentionRun>> {
+        let runs: Vec<RetentionRun> = sqlx::query_as("SELECT * FROM iss_retention_runs ORDER BY id DESC LIMIT $1")
+            .bind(limit)
             .fetch_all(&self.pool)
             .await?;
-        Ok(logs)
+        Ok(runs)
+    }
+
+    /// Totals over all retention runs plus the current raw and rollup row counts:
+    /// `(rolled_up, deleted, raw_rows, rollup_rows)`.
+    pub async fn retention_totals(&self) -> Result<(i64, i64, i64, i64)> {
+        let totals: (i64, i64, i64, i64) = sqlx::query_as(
+            "SELECT COALESCE((SELECT sum(rolled_up) FROM iss_retention_runs), 0)::BIGINT,
+                    COALESCE((SELECT sum(deleted) FROM iss_retention_runs), 0)::BIGINT,
+                    (SELECT count(*) FROM iss_fetch_log),
+                    (SELECT count(*) FROM iss_fetch_log_rollup)"
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(totals)
     }
 }
@@ -1,4 +1,7 @@
 pub mod db;
 pub mod iss_repo;
 pub mod osdr_repo;
-pub mod cache_repo;
\ No newline at end of file
+pub mod cache_repo;
+pub mod tle_repo;
+pub mod geofence_repo;
+pub mod space_repo;
\ No newline at end of file
@@ -1,7 +1,25 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde_json::Value;
-use sqlx::{PgPool, Row};
+use sqlx::{postgres::PgRow, PgConnection, PgPool, Row};
+
+use crate::domain::models::{
+    NewOsdrItem, NewRejectedOsdrItem, OsdrFilter, OsdrItemVersion, OsdrRejectedItem, OsdrSort,
+    OsdrSyncRun, OsdrSyncSummary,
+};
+
+/// Advisory lock key serializing writes to `osdr_items` and `osdr_item_versions`.
+const WRITE_LOCK_KEY: i64 = 0x05D8_0001;
+
+/// `WHERE` clause for `OsdrFilter`, binding status, updated_since, q and withdrawn as `$1..$4`.
+/// `q` must be passed through `like_escape`.
+const FILTER_SQL: &str = "($1::TEXT IS NULL OR lower(status) = lower($1))
+    AND ($2::TIMESTAMPTZ IS NULL OR updated_at >= $2)
+    AND ($3::TEXT IS NULL OR title ILIKE '%' || $3 || '%' OR dataset_id ILIKE '%' || $3 || '%')
+    AND ($4::BOOLEAN IS NULL OR (withdrawn_at IS NOT NULL) = $4)";
+
+/// Items written per `UNNEST` statement during a sync.
+const UPSERT_BATCH_SIZE: usize = 500;
 
 /// Repository for managing OSDR items in the database.
 #[derive(Clone)]
@@ -15,27 +33,116 @@ impl OsdrRepo {
         Self { pool }
     }
 
-    /// Lists the most recent OSDR items.
-    pub async fn list(&self, limit: i64) -> Result<Vec<Value>> {
-        let rows = sqlx::query(
-            "SELECT id, dataset_id, title, status, updated_at, inserted_at, raw
+    /// Lists one page of OSDR items matching `filter`, together with the total number of matches.
+    pub async fn list(
+        &self,
+        filter: &OsdrFilter,
+        sort: OsdrSort,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Value>, i64)> {
+        let sql = format!(
+            "SELECT id, dataset_id, title, status, updated_at, inserted_at, last_seen_at, withdrawn_at, raw,
+                    COUNT(*) OVER () AS total
              FROM osdr_items
-             ORDER BY inserted_at DESC
-             LIMIT $1"
-        ).bind(limit).fetch_all(&self.pool).await?;
+             WHERE {}
+             ORDER BY {}
+             LIMIT $5 OFFSET $6",
+            FILTER_SQL,
+            sort.order_by()
+        );
+        let rows = sqlx::query(&sql)
+            .bind(filter.status.as_deref())
+            .bind(filter.updated_since)
+            .bind(filter.q.as_deref().map(like_escape))
+            .bind(filter.withdrawn)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let total = match rows.first() {
+            Some(r) => r.get::<i64, _>("total"),
+            // An empty page past the end still reports the real total.
+            None if offset > 0 => self.count_matching(filter).await?,
+            None => 0,
+        };
+        let out: Vec<Value> = rows.iter().map(row_to_json).collect();
+        Ok((out, total))
+    }
+
+    /// Gets a stored item by its dataset ID.
+    pub async fn get(&self, dataset_id: &str) -> Result<Option<Value>> {
+        let row = sqlx::query(
+            "SELECT id, dataset_id, title, status, updated_at, inserted_at, last_seen_at, withdrawn_at, raw
+             FROM osdr_items WHERE dataset_id = $1"
+        )
+        .bind(dataset_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.as_ref().map(row_to_json))
+    }
+
+    /// Full-text search over titles and descriptions, best matches first.
+    /// `query` uses web-search syntax (quoted phrases, `or`, `-word`).
+    pub async fn search(&self, query: &str, limit: i64, offset: i64) -> Result<(Vec<Value>, i64)> {
+        let rows = sqlx::query(
+            "SELECT id, dataset_id, title, status, updated_at,
+                    ts_rank_cd(search_tsv, q) AS rank,
+                    ts_headline('english', coalesce(title, ''), q,
+                        'StartSel=<mark>, StopSel=</mark>, HighlightAll=true') AS title_highlight,
+                    ts_headline('english',
+                        coalesce(raw->>'description', raw->>'summary', raw->>'abstract', ''), q,
+                        'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=30, MinWords=10') AS snippet,
+                    COUNT(*) OVER () AS total
+             FROM osdr_items, websearch_to_tsquery('english', $1) AS q
+             WHERE search_tsv @@ q
+             ORDER BY rank DESC, id ASC
+             LIMIT $2 OFFSET $3"
+        )
+        .bind(query)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
 
-        let out: Vec<Value> = rows.into_iter().map(|r| {
+        let total = match rows.first() {
+            Some(r) => r.get::<i64, _>("total"),
+            None if offset > 0 => {
+                sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM osdr_items WHERE search_tsv @@ websearch_to_tsquery('english', $1)"
+                )
+                .bind(query)
+                .fetch_one(&self.pool)
+                .await?
+            }
+            None => 0,
+        };
+        let out: Vec<Value> = rows.iter().map(|r| {
             serde_json::json!({
                 "id": r.get::<i64,_>("id"),
                 "dataset_id": r.get::<Option<String>,_>("dataset_id"),
                 "title": r.get::<Option<String>,_>("title"),
                 "status": r.get::<Option<String>,_>("status"),
                 "updated_at": r.get::<Option<DateTime<Utc>>,_>("updated_at"),
-                "inserted_at": r.get::<DateTime<Utc>, _>("inserted_at"),
-                "raw": r.get::<Value,_>("raw"),
+                "rank": r.get::<f32,_>("rank"),
+                "title_highlight": r.get::<String,_>("title_highlight"),
+                "snippet": r.get::<String,_>("snippet"),
             })
         }).collect();
-        Ok(out)
+        Ok((out, total))
+    }
+
+    /// Counts the items matching `filter`.
+    pub async fn count_matching(&self, filter: &OsdrFilter) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM osdr_items WHERE {}", FILTER_SQL))
+            .bind(filter.status.as_deref())
+            .bind(filter.updated_since)
+            .bind(filter.q.as_deref().map(like_escape))
+            .bind(filter.withdrawn)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count)
     }
 
     /// Gets the total count of items in the osdr_items table.
@@ -46,33 +153,358 @@ impl OsdrRepo {
         Ok(count)
     }
 
-    /// Inserts a new item, or updates it if the dataset_id already exists.
-    pub async fn upsert(
+    /// Dataset counts by status and by month of `updated_at`, plus the total and withdrawn
+    /// counts. Withdrawn datasets are left out of the breakdowns.
+    pub async fn status_stats(&self) -> Result<Value> {
+        let row = sqlx::query(
+            "SELECT
+                (SELECT COUNT(*) FROM osdr_items) AS total,
+                (SELECT COUNT(*) FROM osdr_items WHERE withdrawn_at IS NOT NULL) AS withdrawn,
+                (SELECT COALESCE(jsonb_agg(jsonb_build_object('value', status, 'count', n) ORDER BY n DESC, status), '[]')
+                 FROM (SELECT status, COUNT(*) AS n FROM osdr_items
+                       WHERE withdrawn_at IS NULL GROUP BY status) s) AS by_status,
+                (SELECT COALESCE(jsonb_agg(jsonb_build_object('month', month, 'count', n) ORDER BY month), '[]')
+                 FROM (SELECT to_char(date_trunc('month', updated_at), 'YYYY-MM') AS month, COUNT(*) AS n
+                       FROM osdr_items WHERE withdrawn_at IS NULL AND updated_at IS NOT NULL
+                       GROUP BY 1) m) AS updated_per_month"
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(serde_json::json!({
+            "total": row.get::<i64,_>("total"),
+            "withdrawn": row.get::<i64,_>("withdrawn"),
+            "by_status": row.get::<Value,_>("by_status"),
+            "updated_per_month": row.get::<Value,_>("updated_per_month"),
+        }))
+    }
+
+    /// Raw records of all datasets that are not withdrawn.
+    pub async fn list_active_raw(&self) -> Result<Vec<Value>> {
+        let raws: Vec<Value> = sqlx::query_scalar("SELECT raw FROM osdr_items WHERE withdrawn_at IS NULL")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(raws)
+    }
+
+    /// Gets the sync watermark stored for an upstream source.
+    pub async fn get_watermark(&self, source: &str) -> Result<Option<DateTime<Utc>>> {
+        let watermark: Option<Option<DateTime<Utc>>> = sqlx::query_scalar(
+            "SELECT watermark FROM osdr_sync_state WHERE source = $1"
+        )
+        .bind(source)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(watermark.flatten())
+    }
+
+    /// Upserts `items` in batches and moves the source's watermark, all in one transaction.
+    /// Items whose content hash is unchanged are left untouched; changed ones get a new
+    /// row in `osdr_item_versions`. When `seen` lists every dataset of a complete upstream
+    /// listing, it also refreshes `last_seen_at` and withdraws items missing from
+    /// `withdraw_after` consecutive listings.
+    /// Returns the number of items inserted or changed and the number newly withdrawn.
+    pub async fn store_sync(
         &self,
-        dataset_id: &str,
-        title: Option<&str>,
-        status: Option<&str>,
-        updated_at: Option<DateTime<Utc>>,
-        raw: &Value,
-    ) -> Result<()> {
+        source: &str,
+        items: &[NewOsdrItem],
+        watermark: Option<DateTime<Utc>>,
+        seen: Option<&[String]>,
+        withdraw_after: i32,
+    ) -> Result<(usize, usize)> {
+        let mut tx = self.pool.begin().await?;
+        lock_writes(&mut tx).await?;
+        let written = upsert_items(&mut tx, items).await?;
+
+        let mut withdrawn = 0;
+        if let Some(seen) = seen.filter(|s| !s.is_empty()) {
+            sqlx::query(
+                "UPDATE osdr_items SET last_seen_at = now(), missed_syncs = 0, withdrawn_at = NULL
+                 WHERE dataset_id = ANY($1)"
+            )
+            .bind(seen)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(
+                "UPDATE osdr_items SET missed_syncs = missed_syncs + 1
+                 WHERE dataset_id IS NOT NULL AND NOT (dataset_id = ANY($1))"
+            )
+            .bind(seen)
+            .execute(&mut *tx)
+            .await?;
+            withdrawn = sqlx::query(
+                "UPDATE osdr_items SET withdrawn_at = now()
+                 WHERE withdrawn_at IS NULL AND missed_syncs >= $1"
+            )
+            .bind(withdraw_after)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected() as usize;
+        }
+
         sqlx::query(
-            "INSERT INTO osdr_items(dataset_id, title, status, updated_at, raw)
-             VALUES($1, $2, $3, $4, $5)
-             ON CONFLICT (dataset_id) DO UPDATE
-             SET title = EXCLUDED.title,
-                 status = EXCLUDED.status,
-                 updated_at = EXCLUDED.updated_at,
-                 raw = EXCLUDED.raw,
-                 inserted_at = NOW()"
+            "INSERT INTO osdr_sync_state (source, watermark, last_synced_at) VALUES ($1, $2, now())
+             ON CONFLICT (source) DO UPDATE
+             SET watermark = GREATEST(osdr_sync_state.watermark, EXCLUDED.watermark),
+                 last_synced_at = now()"
         )
-        .bind(dataset_id)
-        .bind(title)
-        .bind(status)
-        .bind(updated_at)
-        .bind(raw)
+        .bind(source)
+        .bind(watermark)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok((written, withdrawn))
+    }
+
+    /// Records the start of a sync run and returns its ID with `true`. While another run is
+    /// still in progress, nothing is recorded and that run's ID is returned with `false`.
+    pub async fn start_run(&self, full: bool) -> Result<(i64, bool)> {
+        // Retried if the other run finishes between the two statements.
+        loop {
+            let started: Option<i64> = sqlx::query_scalar(
+                "INSERT INTO osdr_sync_runs (full_sync) VALUES ($1)
+                 ON CONFLICT ((true)) WHERE status = 'running' DO NOTHING
+                 RETURNING id"
+            )
+            .bind(full)
+            .fetch_optional(&self.pool)
+            .await?;
+            if let Some(id) = started {
+                return Ok((id, true));
+            }
+            let running: Option<i64> = sqlx::query_scalar("SELECT id FROM osdr_sync_runs WHERE status = 'running'")
+                .fetch_optional(&self.pool)
+                .await?;
+            if let Some(id) = running {
+                return Ok((id, false));
+            }
+        }
+    }
+
+    /// Marks a run as finished with its counters, or as failed with `error`.
+    pub async fn finish_run(&self, run_id: i64, summary: &OsdrSyncSummary, error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "UPDATE osdr_sync_runs
+             SET status = CASE WHEN $2::TEXT IS NULL THEN 'succeeded' ELSE 'failed' END,
+                 error = $2, finished_at = now(),
+                 pages = $3, fetched = $4, upserted = $5, unchanged = $6,
+                 skipped = $7, invalid = $8, withdrawn = $9
+             WHERE id = $1"
+        )
+        .bind(run_id)
+        .bind(error)
+        .bind(summary.pages as i32)
+        .bind(summary.fetched as i32)
+        .bind(summary.written as i32)
+        .bind(summary.unchanged as i32)
+        .bind(summary.skipped as i32)
+        .bind(summary.invalid as i32)
+        .bind(summary.withdrawn as i32)
         .execute(&self.pool)
         .await?;
+        Ok(())
+    }
+
+    /// Gets a sync run by ID.
+    pub async fn get_run(&self, run_id: i64) -> Result<Option<OsdrSyncRun>> {
+        let run: Option<OsdrSyncRun> = sqlx::query_as(
+            "SELECT id, full_sync, status, started_at, finished_at, pages, fetched, upserted,
+                    unchanged, skipped, invalid, withdrawn, error
+             FROM osdr_sync_runs WHERE id = $1"
+        )
+        .bind(run_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(run)
+    }
 
+    /// Quarantines rejected items. An item rejected before keeps its row, which gets
+    /// the latest errors and run.
+    pub async fn quarantine(&self, source: &str, run_id: Option<i64>, items: &[NewRejectedOsdrItem]) -> Result<()> {
+        for batch in items.chunks(UPSERT_BATCH_SIZE) {
+            let ids: Vec<Option<&str>> = batch.iter().map(|i| i.dataset_id.as_deref()).collect();
+            let raws: Vec<Value> = batch.iter().map(|i| i.raw.clone()).collect();
+            let errors: Vec<Value> = batch.iter().map(|i| serde_json::json!(i.errors)).collect();
+            sqlx::query(
+                "INSERT INTO osdr_rejected_items (source, content_hash, dataset_id, raw, errors, run_id)
+                 SELECT DISTINCT ON (md5(raw::text)) $1, md5(raw::text), dataset_id, raw, errors, $2
+                 FROM UNNEST($3::TEXT[], $4::JSONB[], $5::JSONB[]) AS t(dataset_id, raw, errors)
+                 ON CONFLICT (content_hash) DO UPDATE
+                 SET errors = EXCLUDED.errors,
+                     dataset_id = EXCLUDED.dataset_id,
+                     run_id = EXCLUDED.run_id,
+                     last_rejected_at = now(),
+                     times_rejected = osdr_rejected_items.times_rejected + 1"
+            )
+            .bind(source)
+            .bind(run_id)
+            .bind(&ids)
+            .bind(&raws)
+            .bind(&errors)
+            .execute(&self.pool)
+            .await?;
+        }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Drops quarantined items for datasets that have since arrived in a valid form.
+    pub async fn release_rejected(&self, dataset_ids: &[String]) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM osdr_rejected_items WHERE dataset_id = ANY($1)")
+            .bind(dataset_ids)
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Lists quarantined items, most recently rejected first, with the total count.
+    pub async fn list_rejected(&self, limit: i64, offset: i64) -> Result<(Vec<OsdrRejectedItem>, i64)> {
+        let items: Vec<OsdrRejectedItem> = sqlx::query_as(
+            "SELECT id, source, dataset_id, raw, errors, run_id, first_rejected_at, last_rejected_at, times_rejected
+             FROM osdr_rejected_items
+             ORDER BY last_rejected_at DESC, id DESC
+             LIMIT $1 OFFSET $2"
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM osdr_rejected_items")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok((items, total))
+    }
+
+    /// Gets every quarantined item, oldest first.
+    pub async fn all_rejected(&self) -> Result<Vec<OsdrRejectedItem>> {
+        let items: Vec<OsdrRejectedItem> = sqlx::query_as(
+            "SELECT id, source, dataset_id, raw, errors, run_id, first_rejected_at, last_rejected_at, times_rejected
+             FROM osdr_rejected_items ORDER BY id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(items)
+    }
+
+    /// Applies a re-validation in one transaction: stores the items that now pass, removes
+    /// the quarantined rows they came from and records the latest errors of the rest.
+    /// Returns the number of items inserted or changed.
+    pub async fn resolve_rejected(
+        &self,
+        accepted: &[NewOsdrItem],
+        released: &[i64],
+        still_rejected: &[(i64, Value)],
+    ) -> Result<usize> {
+        let mut tx = self.pool.begin().await?;
+        lock_writes(&mut tx).await?;
+        let written = upsert_items(&mut tx, accepted).await?;
+        sqlx::query("DELETE FROM osdr_rejected_items WHERE id = ANY($1)")
+            .bind(released)
+            .execute(&mut *tx)
+            .await?;
+        let ids: Vec<i64> = still_rejected.iter().map(|(id, _)| *id).collect();
+        let errors: Vec<Value> = still_rejected.iter().map(|(_, e)| e.clone()).collect();
+        sqlx::query(
+            "UPDATE osdr_rejected_items r SET errors = t.errors
+             FROM UNNEST($1::BIGINT[], $2::JSONB[]) AS t(id, errors)
+             WHERE r.id = t.id"
+        )
+        .bind(&ids)
+        .bind(&errors)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(written)
+    }
+
+    /// Gets all stored versions of a dataset, oldest first.
+    pub async fn list_versions(&self, dataset_id: &str) -> Result<Vec<OsdrItemVersion>> {
+        let versions: Vec<OsdrItemVersion> = sqlx::query_as(
+            "SELECT dataset_id, version, content_hash, title, status, updated_at, raw, recorded_at
+             FROM osdr_item_versions WHERE dataset_id = $1 ORDER BY version ASC"
+        )
+        .bind(dataset_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(versions)
+    }
+}
+
+/// Escapes `LIKE` wildcards so user input matches literally.
+fn like_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn row_to_json(r: &PgRow) -> Value {
+    serde_json::json!({
+        "id": r.get::<i64,_>("id"),
+        "dataset_id": r.get::<Option<String>,_>("dataset_id"),
+        "title": r.get::<Option<String>,_>("title"),
+        "status": r.get::<Option<String>,_>("status"),
+        "updated_at": r.get::<Option<DateTime<Utc>>,_>("updated_at"),
+        "inserted_at": r.get::<DateTime<Utc>, _>("inserted_at"),
+        "last_seen_at": r.get::<Option<DateTime<Utc>>,_>("last_seen_at"),
+        "withdrawn": r.get::<Option<DateTime<Utc>>,_>("withdrawn_at").is_some(),
+        "withdrawn_at": r.get::<Option<DateTime<Utc>>,_>("withdrawn_at"),
+        "raw": r.get::<Value,_>("raw"),
+    })
+}
+
+/// Upserts `items` in batches. Items whose content hash is unchanged are left untouched;
+/// changed ones get a new row in `osdr_item_versions`. Returns the number inserted or changed.
+async fn upsert_items(conn: &mut PgConnection, items: &[NewOsdrItem]) -> Result<usize> {
+    let mut written = 0;
+    for batch in items.chunks(UPSERT_BATCH_SIZE) {
+        let ids: Vec<&str> = batch.iter().map(|i| i.dataset_id.as_str()).collect();
+        let titles: Vec<Option<&str>> = batch.iter().map(|i| i.title.as_deref()).collect();
+        let statuses: Vec<Option<&str>> = batch.iter().map(|i| i.status.as_deref()).collect();
+        let updated: Vec<Option<DateTime<Utc>>> = batch.iter().map(|i| i.updated_at).collect();
+        let raws: Vec<Value> = batch.iter().map(|i| i.raw.clone()).collect();
+
+        let rows: Vec<String> = sqlx::query_scalar(
+            "WITH input AS (
+                SELECT * FROM UNNEST($1::TEXT[], $2::TEXT[], $3::TEXT[], $4::TIMESTAMPTZ[], $5::JSONB[])
+                    AS t(dataset_id, title, status, updated_at, raw)
+             ),
+             upserted AS (
+                INSERT INTO osdr_items (dataset_id, title, status, updated_at, raw, content_hash, last_seen_at)
+                SELECT dataset_id, title, status, updated_at, raw, md5(raw::text), NOW() FROM input
+                ON CONFLICT (dataset_id) WHERE dataset_id IS NOT NULL DO UPDATE
+                SET title = EXCLUDED.title,
+                    status = EXCLUDED.status,
+                    updated_at = EXCLUDED.updated_at,
+                    raw = EXCLUDED.raw,
+                    content_hash = EXCLUDED.content_hash,
+                    inserted_at = NOW()
+                WHERE osdr_items.content_hash IS DISTINCT FROM EXCLUDED.content_hash
+                RETURNING dataset_id, title, status, updated_at, raw, content_hash
+             ),
+             versioned AS (
+                INSERT INTO osdr_item_versions (dataset_id, version, content_hash, title, status, updated_at, raw)
+                SELECT u.dataset_id,
+                       COALESCE((SELECT max(v.version) FROM osdr_item_versions v WHERE v.dataset_id = u.dataset_id), 0) + 1,
+                       u.content_hash, u.title, u.status, u.updated_at, u.raw
+                FROM upserted u
+             )
+             SELECT dataset_id FROM upserted"
+        )
+        .bind(&ids)
+        .bind(&titles)
+        .bind(&statuses)
+        .bind(&updated)
+        .bind(&raws)
+        .fetch_all(&mut *conn)
+        .await?;
+        written += rows.len();
+    }
+    Ok(written)
+}
+
+/// Waits for other OSDR write transactions, so version numbers and missed-sync counters
+/// are never computed from the same snapshot twice. Released at commit or rollback.
+async fn lock_writes(conn: &mut PgConnection) -> Result<()> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(WRITE_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::PgPool;
+use crate::domain::models::SpaceCacheEntry;
+
+/// Repository for the history of space API fetches kept in `space_cache`.
+#[derive(Clone)]
+pub struct SpaceRepo {
+    pool: PgPool,
+}
+
+impl SpaceRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Appends a fetched payload for a source.
+    pub async fn append(&self, source: &str, payload: &Value) -> Result<()> {
+        sqlx::query("INSERT INTO space_cache(source, payload) VALUES($1, $2)")
+            .bind(source)
+            .bind(payload)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Gets the newest stored payload of a source.
+    pub async fn get_latest(&self, source: &str) -> Result<Option<SpaceCacheEntry>> {
+        let entry: Option<SpaceCacheEntry> = sqlx::query_as(
+            "SELECT id, source, fetched_at, payload FROM space_cache
+             WHERE source = $1 ORDER BY fetched_at DESC, id DESC LIMIT 1"
+        )
+        .bind(source)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(entry)
+    }
+
+    /// Lists the payloads of a source fetched within `[from, to]`, newest first.
+    pub async fn get_range(
+        &self,
+        source: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<SpaceCacheEntry>> {
+        let entries: Vec<SpaceCacheEntry> = sqlx::query_as(
+            "SELECT id, source, fetched_at, payload FROM space_cache
+             WHERE source = $1 AND fetched_at BETWEEN $2 AND $3
+             ORDER BY fetched_at DESC, id DESC LIMIT $4"
+        )
+        .bind(source)
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+}
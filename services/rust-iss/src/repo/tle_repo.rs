@@ -0,0 +1,53 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use crate::domain::models::TleSet;
+
+/// Repository for managing TLE element sets in the database.
+#[derive(Clone)]
+pub struct TleRepo {
+    pool: PgPool,
+}
+
+impl TleRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Stores an element set. Returns `false` if the same epoch is already known.
+    pub async fn insert(
+        &self,
+        norad_id: i64,
+        name: Option<&str>,
+        line1: &str,
+        line2: &str,
+        epoch: DateTime<Utc>,
+        source: &str,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "INSERT INTO tle_sets(norad_id, name, line1, line2, epoch, source)
+             VALUES($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (norad_id, epoch) DO NOTHING"
+        )
+        .bind(norad_id)
+        .bind(name)
+        .bind(line1)
+        .bind(line2)
+        .bind(epoch)
+        .bind(source)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Gets the element set with the newest epoch for a satellite.
+    pub async fn get_latest(&self, norad_id: i64) -> Result<Option<TleSet>> {
+        let tle: Option<TleSet> = sqlx::query_as(
+            "SELECT * FROM tle_sets WHERE norad_id = $1 ORDER BY epoch DESC LIMIT 1"
+        )
+        .bind(norad_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(tle)
+    }
+}
@@ -1,11 +1,11 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use axum::{routing::get, Router};
+use axum::{routing::{delete, get, post}, Router};
 use tower_governor::{governor::GovernorConfigBuilder, GovernorLayer};
 
 use crate::domain::models::AppState;
-use crate::handlers::{health, iss, osdr, space};
+use crate::handlers::{geofence, health, iss, osdr, satellites, space};
 
 pub fn create_router(state: AppState) -> Router {
     // Create a rate limiter configuration
@@ -22,13 +22,47 @@ pub fn create_router(state: AppState) -> Router {
         .route("/last", get(iss::last_iss))
         .route("/fetch", get(iss::trigger_iss))
         .route("/iss/trend", get(iss::iss_trend))
+        .route("/iss/history", get(iss::iss_history))
+        .route("/iss/retention", get(iss::iss_retention))
+        .route("/iss/stream", get(iss::iss_stream))
+        .route("/iss/ws", get(iss::iss_ws))
+        .route("/iss/export/geojson", get(iss::iss_export_geojson))
+        .route("/iss/export/kml", get(iss::iss_export_kml))
+        .route("/iss/export/gpx", get(iss::iss_export_gpx))
+        .route("/iss/predict", get(iss::iss_predict))
+        .route("/iss/passes", get(iss::iss_passes))
+        .route("/iss/geofence-events", get(geofence::iss_geofence_events))
+        // Geofences
+        .route("/geofences", get(geofence::list_geofences).post(geofence::create_geofence))
+        .route("/geofences/:id", delete(geofence::delete_geofence))
+        // Tracked satellites (the ISS routes above are aliases for NORAD 25544)
+        .route("/satellites", get(satellites::list_satellites))
+        .route("/satellites/:id/last", get(satellites::satellite_last))
+        .route("/satellites/:id/fetch", get(satellites::satellite_fetch))
+        .route("/satellites/:id/trend", get(satellites::satellite_trend))
+        .route("/satellites/:id/history", get(satellites::satellite_history))
+        .route("/satellites/:id/stream", get(satellites::satellite_stream))
+        .route("/satellites/:id/ws", get(satellites::satellite_ws))
+        // v2: typed position responses without the raw upstream payload
+        .route("/v2/iss/last", get(iss::last_iss_v2))
+        .route("/v2/satellites/:id/last", get(satellites::satellite_last_v2))
         // OSDR
         .route("/osdr/sync", get(osdr::osdr_sync))
+        .route("/osdr/sync/:run_id", get(osdr::osdr_sync_status))
         .route("/osdr/list", get(osdr::osdr_list))
+        .route("/osdr/search", get(osdr::osdr_search))
+        .route("/osdr/stats", get(osdr::osdr_stats))
+        .route("/osdr/mapping/dry-run", post(osdr::osdr_mapping_dry_run))
+        .route("/osdr/rejected", get(osdr::osdr_rejected))
+        .route("/osdr/rejected/revalidate", post(osdr::osdr_revalidate))
+        .route("/osdr/:dataset_id", get(osdr::osdr_detail))
+        .route("/osdr/:dataset_id/history", get(osdr::osdr_history))
         // Space cache
         .route("/space/:src/latest", get(space::space_latest))
+        .route("/space/:src/history", get(space::space_history))
         .route("/space/refresh", get(space::space_refresh))
         .route("/space/summary", get(space::space_summary))
+        .route("/space/terminator", get(space::space_terminator))
         // .layer(GovernorLayer {
         //     config: Arc::new(governor_conf),
         // })
@@ -0,0 +1,188 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use tracing::info;
+
+use crate::domain::models::{Geofence, GeofenceEvent, GeofenceShape, IssHistoryPoint, NewGeofence};
+use crate::repo::geofence_repo::GeofenceRepo;
+use crate::services::iss_service::IssService;
+
+/// Service for managing geofences and detecting enter/exit transitions.
+#[derive(Clone)]
+pub struct GeofenceService {
+    repo: GeofenceRepo,
+}
+
+impl GeofenceService {
+    pub fn new(repo: GeofenceRepo) -> Self {
+        Self { repo }
+    }
+
+    /// Checks that a geofence definition is usable, returning a message describing the problem.
+    pub fn validate(geofence: &NewGeofence) -> std::result::Result<(), String> {
+        if geofence.name.trim().is_empty() {
+            return Err("`name` must not be empty".to_string());
+        }
+        match &geofence.shape {
+            GeofenceShape::Circle { lat, lon, radius_km } => {
+                if !(-90.0..=90.0).contains(lat) || !(-180.0..=180.0).contains(lon) {
+                    return Err("circle center is out of range".to_string());
+                }
+                if *radius_km <= 0.0 {
+                    return Err("`radius_km` must be positive".to_string());
+                }
+            }
+            GeofenceShape::Polygon { coordinates } => {
+                if coordinates.len() < 3 {
+                    return Err("a polygon needs at least 3 vertices".to_string());
+                }
+                if let Some(i) = coordinates
+                    .iter()
+                    .position(|[lon, lat]| !(-90.0..=90.0).contains(lat) || !(-180.0..=180.0).contains(lon))
+                {
+                    return Err(format!("polygon vertex {} is out of range", i));
+                }
+                // An edge spanning more than half the globe is only short across the antimeridian.
+                let crosses = (0..coordinates.len()).any(|i| {
+                    let next = coordinates[(i + 1) % coordinates.len()];
+                    (next[0] - coordinates[i][0]).abs() > 180.0
+                });
+                if crosses {
+                    return Err("polygons must not cross the antimeridian".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn create(&self, geofence: &NewGeofence) -> Result<Geofence> {
+        self.repo.create(geofence.name.trim(), &geofence.shape).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<Geofence>> {
+        self.repo.list().await
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<bool> {
+        self.repo.delete(id).await
+    }
+
+    pub async fn events(
+        &self,
+        satellite_id: i64,
+        geofence_id: Option<i64>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<GeofenceEvent>> {
+        self.repo.list_events(satellite_id, geofence_id, from, to, limit).await
+    }
+
+    /// Compares two consecutive samples of a satellite against every geofence
+    /// and records an `enter` or `exit` event for each boundary crossed.
+    pub async fn evaluate(
+        &self,
+        satellite_id: i64,
+        prev: &IssHistoryPoint,
+        current: &IssHistoryPoint,
+    ) -> Result<usize> {
+        let mut recorded = 0;
+        for geofence in self.repo.list().await? {
+            let was_inside = Self::contains(&geofence.shape, prev.lat, prev.lon);
+            let is_inside = Self::contains(&geofence.shape, current.lat, current.lon);
+            if was_inside == is_inside {
+                continue;
+            }
+
+            let event = if is_inside { "enter" } else { "exit" };
+            self.repo
+                .create_event(geofence.id, satellite_id, event, current.lat, current.lon, current.fetched_at)
+                .await?;
+            info!("Satellite {} {} geofence '{}'", satellite_id, if is_inside { "entered" } else { "left" }, geofence.name);
+            recorded += 1;
+        }
+        Ok(recorded)
+    }
+
+    // --- Private Helper Functions ---
+
+    fn contains(shape: &GeofenceShape, lat: f64, lon: f64) -> bool {
+        match shape {
+            GeofenceShape::Circle { lat: c_lat, lon: c_lon, radius_km } => {
+                IssService::haversine_km(*c_lat, *c_lon, lat, lon) <= *radius_km
+            }
+            GeofenceShape::Polygon { coordinates } => {
+                // Even-odd ray casting in the lon/lat plane.
+                let mut inside = false;
+                let mut j = coordinates.len() - 1;
+                for i in 0..coordinates.len() {
+                    let [xi, yi] = coordinates[i];
+                    let [xj, yj] = coordinates[j];
+                    if (yi > lat) != (yj > lat) && lon < (xj - xi) * (lat - yi) / (yj - yi) + xi {
+                        inside = !inside;
+                    }
+                    j = i;
+                }
+                inside
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon(coordinates: &[[f64; 2]]) -> GeofenceShape {
+        GeofenceShape::Polygon { coordinates: coordinates.to_vec() }
+    }
+
+    fn validate(shape: GeofenceShape) -> std::result::Result<(), String> {
+        GeofenceService::validate(&NewGeofence { name: "zone".to_string(), shape })
+    }
+
+    #[test]
+    fn validate_accepts_sane_shapes() {
+        assert!(validate(GeofenceShape::Circle { lat: 55.75, lon: 37.62, radius_km: 500.0 }).is_ok());
+        assert!(validate(polygon(&[[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]])).is_ok());
+        assert!(validate(polygon(&[[170.0, -10.0], [180.0, -10.0], [180.0, 10.0]])).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_bad_shapes() {
+        assert!(validate(GeofenceShape::Circle { lat: 95.0, lon: 0.0, radius_km: 10.0 }).is_err());
+        assert!(validate(GeofenceShape::Circle { lat: 0.0, lon: 0.0, radius_km: 0.0 }).is_err());
+        assert!(validate(polygon(&[[0.0, 0.0], [10.0, 0.0]])).is_err());
+        assert_eq!(
+            validate(polygon(&[[0.0, 0.0], [500.0, 0.0], [0.0, 900.0]])),
+            Err("polygon vertex 1 is out of range".to_string())
+        );
+        assert_eq!(
+            validate(polygon(&[[170.0, -10.0], [-170.0, -10.0], [-170.0, 10.0], [170.0, 10.0]])),
+            Err("polygons must not cross the antimeridian".to_string())
+        );
+        assert!(GeofenceService::validate(&NewGeofence {
+            name: " ".to_string(),
+            shape: GeofenceShape::Circle { lat: 0.0, lon: 0.0, radius_km: 10.0 },
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn contains_circle() {
+        let circle = GeofenceShape::Circle { lat: 0.0, lon: 0.0, radius_km: 200.0 };
+        assert!(GeofenceService::contains(&circle, 1.0, 1.0));
+        assert!(!GeofenceService::contains(&circle, 2.0, 0.0));
+    }
+
+    #[test]
+    fn contains_polygon() {
+        // A concave "L" shape: the notch at the top right is outside.
+        let shape = polygon(&[[0.0, 0.0], [20.0, 0.0], [20.0, 10.0], [10.0, 10.0], [10.0, 20.0], [0.0, 20.0]]);
+        assert!(GeofenceService::contains(&shape, 5.0, 5.0));
+        assert!(GeofenceService::contains(&shape, 15.0, 5.0));
+        assert!(GeofenceService::contains(&shape, 5.0, 15.0));
+        assert!(!GeofenceService::contains(&shape, 15.0, 15.0));
+        assert!(!GeofenceService::contains(&shape, -5.0, 5.0));
+        assert!(!GeofenceService::contains(&shape, 5.0, 25.0));
+    }
+}
@@ -1,88 +1,218 @@
+use std::sync::Arc;
+
 use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
 use serde_json::Value;
-use crate::domain::models::Trend;
+use tokio::sync::broadcast;
+use tracing::{error, warn};
+use crate::domain::models::{
+    IssFetchLog, IssHistoryPoint, IssPosition, IssPositionResponse, Trend, ISS_NORAD_ID,
+};
+use crate::domain::quality::{assess, Sample, SampleQuality, SpeedCheck};
+use crate::domain::solar::illumination;
 use crate::repo::iss_repo::IssRepo;
+use crate::services::geofence_service::GeofenceService;
+use crate::services::position_provider::{IssPositionProvider, ProviderFix};
+
+/// Upper bound on the number of samples aggregated into a single trend.
+const TREND_MAX_SAMPLES: i64 = 5000;
+/// Longest trend window accepted through `minutes` (30 days).
+const TREND_MAX_MINUTES: i64 = 60 * 24 * 30;
+/// An interval longer than this multiple of the median interval counts as a gap.
+const TREND_GAP_FACTOR: f64 = 1.5;
+/// Rows buffered per live-stream subscriber before slow clients start skipping updates.
+const STREAM_BUFFER: usize = 16;
 
-/// Service for handling ISS-related business logic.
+/// Service for tracking the ISS and other satellites by NORAD ID.
 #[derive(Clone)]
 pub struct IssService {
     repo: IssRepo,
-    client: reqwest::Client,
-    iss_url: String,
+    providers: Arc<Vec<Box<dyn IssPositionProvider>>>,
+    satellites: Vec<i64>,
+    trend_samples: i64,
+    speed_check: SpeedCheck,
+    updates: broadcast::Sender<IssFetchLog>,
+    geofences: GeofenceService,
 }
 
 impl IssService {
-    pub fn new(repo: IssRepo, iss_url: String) -> Self {
+    /// `providers` are tried in order on every fetch until one succeeds.
+    pub fn new(
+        repo: IssRepo,
+        providers: Vec<Box<dyn IssPositionProvider>>,
+        satellites: Vec<i64>,
+        trend_samples: i64,
+        speed_check: SpeedCheck,
+        geofences: GeofenceService,
+    ) -> Self {
+        let (updates, _) = broadcast::channel(STREAM_BUFFER);
         Self {
             repo,
-            client: reqwest::Client::new(),
-            iss_url,
+            providers: Arc::new(providers),
+            satellites,
+            trend_samples,
+            speed_check,
+            updates,
+            geofences,
         }
     }
 
-    /// Fetches the current ISS position and stores it in the database.
-    pub async fn fetch_and_store_iss(&self) -> Result<()> {
-        let resp = self.client.get(&self.iss_url)
-            .timeout(std::time::Duration::from_secs(20))
-            .send()
-            .await?;
-        
-        let json: Value = resp.json().await?;
-        self.repo.create_log(&self.iss_url, &json).await?;
-        Ok(())
+    /// NORAD IDs of all tracked satellites.
+    pub fn tracked_satellites(&self) -> &[i64] {
+        &self.satellites
     }
 
-    /// Gets the most recent ISS log from the database.
-    pub async fn get_last_iss(&self) -> Result<Option<Value>> {
-        self.repo.get_last().await
+    pub fn is_tracked(&self, satellite_id: i64) -> bool {
+        self.satellites.contains(&satellite_id)
     }
-    
-    /// Calculates the trend of ISS movement based on the last two data points.
-    pub async fn get_trend(&self) -> Result<Trend> {
-        let rows = self.repo.get_last_two().await?;
 
-        if rows.len() < 2 {
-            return Ok(Trend {
-                movement: false, delta_km: 0.0, dt_sec: 0.0, velocity_kmh: None,
-                from_time: None, to_time: None, from_lat: None,
-                from_lon: None, to_lat: None, to_lon: None,
-            });
+    /// Fetches the current position of a satellite from the first provider that answers,
+    /// flags it if it is inconsistent with the previous good sample, stores it in the database,
+    /// checks unflagged samples against the geofences and publishes the new row to live-stream subscribers.
+    pub async fn fetch_and_store(&self, satellite_id: i64) -> Result<()> {
+        let ProviderFix { source_url: url, payload: json } = self.fetch_from_providers(satellite_id).await?;
+        let position = IssPosition::parse(&json)
+            .map_err(|e| warn!("Satellite {} payload failed validation: {}", satellite_id, e))
+            .ok();
+        let prev = self.repo.get_last_good(satellite_id).await?;
+        let quality = match (&prev, &position) {
+            (_, None) => SampleQuality::Invalid,
+            (Some(prev), Some(_)) => self.assess_sample(prev, &json, Utc::now()),
+            (None, Some(_)) => SampleQuality::Ok,
+        };
+        if quality != SampleQuality::Ok {
+            warn!("Satellite {} sample flagged as {}", satellite_id, quality.as_str());
         }
+        let log = self.repo.create_log(satellite_id, &url, &json, position.as_ref(), quality.as_str()).await?;
 
-        let t2 = rows[0].fetched_at;
-        let t1 = rows[1].fetched_at;
-        let p2 = &rows[0].payload;
-        let p1 = &rows[1].payload;
-
-        let lat1 = Self::num_from_json(&p1["latitude"]);
-        let lon1 = Self::num_from_json(&p1["longitude"]);
-        let lat2 = Self::num_from_json(&p2["latitude"]);
-        let lon2 = Self::num_from_json(&p2["longitude"]);
-        let v2 = Self::num_from_json(&p2["velocity"]);
-
-        let mut delta_km = 0.0;
-        let mut movement = false;
-        if let (Some(lat1), Some(lon1), Some(lat2), Some(lon2)) = (lat1, lon1, lat2, lon2) {
-            delta_km = Self::haversine_km(lat1, lon1, lat2, lon2);
-            movement = delta_km > 0.1;
+        if quality == SampleQuality::Ok {
+            let prev_point = prev.as_ref().and_then(Self::point_from_log);
+            if let (Some(prev), Some(current)) = (prev_point, Self::point_from_log(&log)) {
+                // A failed geofence check must not lose the sample itself.
+                if let Err(e) = self.geofences.evaluate(satellite_id, &prev, &current).await {
+                    error!("Geofence evaluation for satellite {} failed: {:?}", satellite_id, e);
+                }
+            }
         }
-        
-        let dt_sec = (t2 - t1).num_milliseconds() as f64 / 1000.0;
 
-        Ok(Trend {
-            movement, delta_km, dt_sec, velocity_kmh: v2,
-            from_time: Some(t1), to_time: Some(t2),
-            from_lat: lat1, from_lon: lon1, to_lat: lat2, to_lon: lon2,
-        })
+        // Sending only fails when nobody is subscribed, which is fine.
+        let _ = self.updates.send(log);
+        Ok(())
     }
 
-    // --- Private Helper Functions ---
+    /// Subscribes to rows written by `fetch_and_store` for all satellites.
+    pub fn subscribe(&self) -> broadcast::Receiver<IssFetchLog> {
+        self.updates.subscribe()
+    }
 
-    fn num_from_json(v: &Value) -> Option<f64> {
-        v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+    /// Gets the most recent log of a satellite from the database.
+    pub async fn get_last(&self, satellite_id: i64) -> Result<Option<Value>> {
+        self.repo.get_last(satellite_id).await
     }
 
-    fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    /// Gets the most recent position of a satellite that passed validation.
+    pub async fn get_last_position(&self, satellite_id: i64) -> Result<Option<IssPositionResponse>> {
+        let log = self.repo.get_last_position(satellite_id).await?;
+        Ok(log.as_ref().and_then(IssPositionResponse::from_log))
+    }
+
+    /// Gets a satellite track between `from` and `to`, capped at `limit` points,
+    /// with the lighting conditions of every point.
+    pub async fn get_history(
+        &self,
+        satellite_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+        exclude_flagged: bool,
+    ) -> Result<Vec<IssHistoryPoint>> {
+        let mut points = self.repo.get_range(satellite_id, from, to, limit, exclude_flagged).await?;
+        for p in &mut points {
+            p.illumination = Some(illumination(p.lat, p.lon, p.altitude.unwrap_or(0.0), p.fetched_at));
+        }
+        Ok(points)
+    }
+
+    /// Calculates the movement trend of a satellite over a window of recent samples.
+    /// The window is the last `minutes` of data if given, otherwise the last `samples` rows.
+    pub async fn get_trend(
+        &self,
+        satellite_id: i64,
+        samples: Option<i64>,
+        minutes: Option<i64>,
+        exclude_flagged: bool,
+    ) -> Result<Trend> {
+        let now = Utc::now();
+        let (from, limit) = match minutes {
+            Some(m) => (now - Duration::minutes(m.clamp(1, TREND_MAX_MINUTES)), TREND_MAX_SAMPLES),
+            None => (
+                DateTime::<Utc>::UNIX_EPOCH,
+                samples.unwrap_or(self.trend_samples).clamp(2, TREND_MAX_SAMPLES),
+            ),
+        };
+        let points = self.repo.get_range(satellite_id, from, now, limit, exclude_flagged).await?;
+        Ok(Self::trend_from_points(&points))
+    }
+
+    /// Aggregates a chronologically ordered series of positions into a `Trend`.
+    fn trend_from_points(points: &[IssHistoryPoint]) -> Trend {
+        let (first, last) = match (points.first(), points.last()) {
+            (Some(f), Some(l)) if points.len() >= 2 => (f, l),
+            _ => return Trend { samples: points.len(), ..Default::default() },
+        };
+
+        let total_distance_km: f64 = points
+            .windows(2)
+            .map(|w| Self::haversine_km(w[0].lat, w[0].lon, w[1].lat, w[1].lon))
+            .sum();
+
+        // A gap is any interval noticeably longer than the typical (median) polling interval.
+        let mut intervals: Vec<f64> = points
+            .windows(2)
+            .map(|w| (w[1].fetched_at - w[0].fetched_at).num_milliseconds() as f64 / 1000.0)
+            .collect();
+        let max_gap_sec = intervals.iter().cloned().reduce(f64::max);
+        let gap_threshold = {
+            let mut sorted = intervals.clone();
+            sorted.sort_by(|a, b| a.total_cmp(b));
+            sorted[sorted.len() / 2] * TREND_GAP_FACTOR
+        };
+        intervals.retain(|dt| *dt > gap_threshold);
+
+        let velocities: Vec<f64> = points.iter().filter_map(|p| p.velocity).collect();
+        let mean_velocity_kmh = if velocities.is_empty() {
+            None
+        } else {
+            Some(velocities.iter().sum::<f64>() / velocities.len() as f64)
+        };
+
+        let delta_km = Self::haversine_km(first.lat, first.lon, last.lat, last.lon);
+        let dt_sec = (last.fetched_at - first.fetched_at).num_milliseconds() as f64 / 1000.0;
+
+        Trend {
+            movement: delta_km > 0.1,
+            delta_km,
+            dt_sec,
+            velocity_kmh: last.velocity,
+            from_time: Some(first.fetched_at),
+            to_time: Some(last.fetched_at),
+            from_lat: Some(first.lat),
+            from_lon: Some(first.lon),
+            to_lat: Some(last.lat),
+            to_lon: Some(last.lon),
+            samples: points.len(),
+            total_distance_km,
+            mean_velocity_kmh,
+            min_velocity_kmh: velocities.iter().cloned().reduce(f64::min),
+            max_velocity_kmh: velocities.iter().cloned().reduce(f64::max),
+            altitude_drift_km: last.altitude.zip(first.altitude).map(|(a2, a1)| a2 - a1),
+            gaps: intervals.len(),
+            max_gap_sec,
+        }
+    }
+
+    /// Great-circle distance in kilometers between two points given in degrees.
+    pub fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
         const R: f64 = 6371.0; // Earth radius in kilometers
         let d_lat = (lat2 - lat1).to_radians();
         let d_lon = (lon2 - lon1).to_radians();
@@ -90,4 +220,125 @@ impl IssService {
             + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin() * (d_lon / 2.0).sin();
         2.0 * R * a.sqrt().asin()
     }
+
+    // --- Private Helper Functions ---
+
+    /// Tries each provider in priority order and returns the first successful reading.
+    async fn fetch_from_providers(&self, satellite_id: i64) -> Result<ProviderFix> {
+        let mut failures = Vec::new();
+        for provider in self.providers.iter() {
+            match provider.fetch(satellite_id).await {
+                Ok(fix) => return Ok(fix),
+                Err(e) => {
+                    warn!("Provider {} failed for satellite {}: {:?}", provider.name(), satellite_id, e);
+                    failures.push(format!("{}: {}", provider.name(), e));
+                }
+            }
+        }
+        anyhow::bail!("all position providers failed for satellite {} ({})", satellite_id, failures.join("; "))
+    }
+
+    /// Judges a freshly fetched payload against the previous good row. The expected speed
+    /// is the ISS's, so other satellites only get the timestamp checks.
+    fn assess_sample(&self, prev: &IssFetchLog, payload: &Value, fetched_at: DateTime<Utc>) -> SampleQuality {
+        match (
+            Sample::from_payload(&prev.payload, prev.fetched_at),
+            Sample::from_payload(payload, fetched_at),
+        ) {
+            (Some(a), Some(b)) => {
+                let distance_km = Self::haversine_km(a.lat, a.lon, b.lat, b.lon);
+                let check = (prev.satellite_id == ISS_NORAD_ID).then_some(self.speed_check);
+                assess(&a, &b, check, distance_km)
+            }
+            // Payloads without coordinates carry nothing to judge.
+            _ => SampleQuality::Ok,
+        }
+    }
+
+    /// Extracts a track point from a stored row, if it has typed coordinates.
+    fn point_from_log(log: &IssFetchLog) -> Option<IssHistoryPoint> {
+        Some(IssHistoryPoint {
+            lat: log.lat?,
+            lon: log.lon?,
+            altitude: log.altitude,
+            velocity: log.velocity,
+            fetched_at: log.fetched_at,
+            quality: log.quality.clone(),
+            illumination: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// A sample `secs` seconds into the window.
+    fn point(secs: i64, lat: f64, lon: f64) -> IssHistoryPoint {
+        IssHistoryPoint {
+            lat,
+            lon,
+            altitude: Some(420.0 + secs as f64 / 600.0),
+            velocity: Some(27_600.0 + secs as f64),
+            fetched_at: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap() + Duration::seconds(secs),
+            quality: "ok".to_string(),
+            illumination: None,
+        }
+    }
+
+    #[test]
+    fn fewer_than_two_samples_give_empty_trend() {
+        let empty = IssService::trend_from_points(&[]);
+        assert_eq!(empty.samples, 0);
+        assert!(!empty.movement);
+        assert!(empty.from_time.is_none());
+
+        let single = IssService::trend_from_points(&[point(0, 10.0, 20.0)]);
+        assert_eq!(single.samples, 1);
+        assert_eq!(single.gaps, 0);
+        assert!(single.max_gap_sec.is_none());
+        assert!(single.velocity_kmh.is_none());
+    }
+
+    #[test]
+    fn aggregates_the_window() {
+        let trend = IssService::trend_from_points(&[point(0, 0.0, 0.0), point(120, 0.0, 4.0), point(240, 0.0, 8.0)]);
+        assert_eq!(trend.samples, 3);
+        assert!(trend.movement);
+        assert_eq!(trend.dt_sec, 240.0);
+        assert!((trend.delta_km - trend.total_distance_km).abs() < 1e-6);
+        assert_eq!(trend.from_lon, Some(0.0));
+        assert_eq!(trend.to_lon, Some(8.0));
+        assert_eq!(trend.velocity_kmh, Some(27_840.0));
+        assert_eq!(trend.mean_velocity_kmh, Some(27_720.0));
+        assert_eq!(trend.min_velocity_kmh, Some(27_600.0));
+        assert_eq!(trend.max_velocity_kmh, Some(27_840.0));
+        assert!((trend.altitude_drift_km.unwrap() - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn movement_compares_first_and_last_sample() {
+        let trend = IssService::trend_from_points(&[point(0, 0.0, 0.0), point(120, 0.0, 4.0), point(240, 0.0, 0.0)]);
+        assert!(trend.total_distance_km > 800.0);
+        assert_eq!(trend.delta_km, 0.0);
+        assert!(!trend.movement);
+    }
+
+    #[test]
+    fn gaps_are_intervals_well_above_the_median() {
+        let points = [point(0, 0.0, 0.0), point(120, 0.0, 1.0), point(240, 0.0, 2.0), point(360, 0.0, 3.0), point(960, 0.0, 4.0)];
+        let trend = IssService::trend_from_points(&points);
+        assert_eq!(trend.gaps, 1);
+        assert_eq!(trend.max_gap_sec, Some(600.0));
+
+        // Median 60 s, threshold 90 s: 100 s is a gap.
+        let trend = IssService::trend_from_points(&[point(0, 0.0, 0.0), point(60, 0.0, 1.0), point(120, 0.0, 2.0), point(220, 0.0, 3.0)]);
+        assert_eq!(trend.gaps, 1);
+
+        // Median 170 s, threshold 255 s: 200 s is ordinary jitter.
+        let trend = IssService::trend_from_points(&[point(0, 0.0, 0.0), point(120, 0.0, 1.0), point(290, 0.0, 2.0), point(490, 0.0, 3.0)]);
+        assert_eq!(trend.gaps, 0);
+        assert_eq!(trend.max_gap_sec, Some(200.0));
+    }
 }
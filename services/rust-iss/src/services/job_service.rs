@@ -3,7 +3,8 @@ use tokio::time::{self, Duration};
 use tracing::{error, info};
 
 use crate::services::{
-    iss_service::IssService, osdr_service::OsdrService, space_service::SpaceService,
+    iss_service::IssService, orbit_service::OrbitService, osdr_service::OsdrService,
+    retention_service::RetentionService, space_service::SpaceService,
 };
 
 /// Service responsible for managing all periodic background jobs.
@@ -12,12 +13,17 @@ pub struct JobService {
     iss_service: Arc<IssService>,
     osdr_service: Arc<OsdrService>,
     space_service: Arc<SpaceService>,
+    orbit_service: Arc<OrbitService>,
+    retention_service: Arc<RetentionService>,
     every_iss: u64,
     every_osdr: u64,
     every_apod: u64,
     every_neo: u64,
     every_donki: u64,
     every_spacex: u64,
+    every_tle: u64,
+    every_retention: u64,
+    every_partition: u64,
 }
 
 impl JobService {
@@ -26,23 +32,33 @@ impl JobService {
         iss_service: Arc<IssService>,
         osdr_service: Arc<OsdrService>,
         space_service: Arc<SpaceService>,
+        orbit_service: Arc<OrbitService>,
+        retention_service: Arc<RetentionService>,
         every_iss: u64,
         every_osdr: u64,
         every_apod: u64,
         every_neo: u64,
         every_donki: u64,
         every_spacex: u64,
+        every_tle: u64,
+        every_retention: u64,
+        every_partition: u64,
     ) -> Self {
         Self {
             iss_service,
             osdr_service,
             space_service,
+            orbit_service,
+            retention_service,
             every_iss,
             every_osdr,
             every_apod,
             every_neo,
             every_donki,
             every_spacex,
+            every_tle,
+            every_retention,
+            every_partition,
         }
     }
 
@@ -50,35 +66,40 @@ impl JobService {
     pub fn spawn_all_jobs(self: Arc<Self>) {
         info!("Spawning all background jobs...");
 
-        self.spawn_iss_job();
+        self.spawn_satellite_jobs();
         self.spawn_osdr_job();
         self.spawn_apod_job();
         self.spawn_neo_job();
         self.spawn_donki_job();
         self.spawn_spacex_job();
+        self.spawn_tle_job();
+        self.spawn_retention_job();
+        self.spawn_partition_job();
 
         info!("All background jobs have been spawned.");
     }
 
-    fn spawn_iss_job(&self) {
-        let service = self.iss_service.clone();
-        let period = self.every_iss;
-        tokio::spawn(async move {
-            if period == 0 { return; }
-            let mut interval = time::interval(Duration::from_secs(period));
+    fn spawn_satellite_jobs(&self) {
+        for &satellite_id in self.iss_service.tracked_satellites() {
+            let service = self.iss_service.clone();
+            let period = self.every_iss;
+            tokio::spawn(async move {
+                if period == 0 { return; }
+                let mut interval = time::interval(Duration::from_secs(period));
 
-            // Run once immediately
-            if let Err(e) = service.fetch_and_store_iss().await {
-                error!("Initial ISS fetch job failed: {:?}", e);
-            }
+                // Run once immediately
+                if let Err(e) = service.fetch_and_store(satellite_id).await {
+                    error!("Initial fetch job for satellite {} failed: {:?}", satellite_id, e);
+                }
 
-            loop {
-                interval.tick().await;
-                if let Err(e) = service.fetch_and_store_iss().await {
-                    error!("ISS fetch job failed: {:?}", e);
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = service.fetch_and_store(satellite_id).await {
+                        error!("Fetch job for satellite {} failed: {:?}", satellite_id, e);
+                    }
                 }
-            }
-        });
+            });
+        }
     }
 
     fn spawn_osdr_job(&self) {
@@ -89,13 +110,13 @@ impl JobService {
             let mut interval = time::interval(Duration::from_secs(period));
 
             // Run once immediately
-            if let Err(e) = service.fetch_and_store_osdr().await {
+            if let Err(e) = service.fetch_and_store_osdr(false).await {
                 error!("Initial OSDR fetch job failed: {:?}", e);
             }
 
             loop {
                 interval.tick().await;
-                if let Err(e) = service.fetch_and_store_osdr().await {
+                if let Err(e) = service.fetch_and_store_osdr(false).await {
                     error!("OSDR fetch job failed: {:?}", e);
                 }
             }
@@ -185,4 +206,67 @@ impl JobService {
             }
         });
     }
+
+    fn spawn_tle_job(&self) {
+        let service = self.orbit_service.clone();
+        let period = self.every_tle;
+        tokio::spawn(async move {
+            if period == 0 { return; }
+            let mut interval = time::interval(Duration::from_secs(period));
+
+            // Run once immediately
+            if let Err(e) = service.fetch_and_store_tle().await {
+                error!("Initial TLE fetch job failed: {:?}", e);
+            }
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.fetch_and_store_tle().await {
+                    error!("TLE fetch job failed: {:?}", e);
+                }
+            }
+        });
+    }
+
+    fn spawn_retention_job(&self) {
+        let service = self.retention_service.clone();
+        let period = self.every_retention;
+        tokio::spawn(async move {
+            if period == 0 { return; }
+            let mut interval = time::interval(Duration::from_secs(period));
+
+            // Run once immediately
+            if let Err(e) = service.run().await {
+                error!("Initial ISS retention job failed: {:?}", e);
+            }
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.run().await {
+                    error!("ISS retention job failed: {:?}", e);
+                }
+            }
+        });
+    }
+
+    fn spawn_partition_job(&self) {
+        let service = self.retention_service.clone();
+        let period = self.every_partition;
+        tokio::spawn(async move {
+            if period == 0 { return; }
+            let mut interval = time::interval(Duration::from_secs(period));
+
+            // Run once immediately
+            if let Err(e) = service.ensure_partitions().await {
+                error!("Initial ISS partition job failed: {:?}", e);
+            }
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.ensure_partitions().await {
+                    error!("ISS partition job failed: {:?}", e);
+                }
+            }
+        });
+    }
 }
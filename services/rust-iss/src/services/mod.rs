@@ -1,4 +1,8 @@
 pub mod iss_service;
 pub mod osdr_service;
 pub mod space_service;
-pub mod job_service;
\ No newline at end of file
+pub mod job_service;
+pub mod orbit_service;
+pub mod geofence_service;
+pub mod retention_service;
+pub mod position_provider;
\ No newline at end of file
@@ -0,0 +1,133 @@
+use std::sync::{Arc, RwLock};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use tracing::{info, warn};
+
+use crate::domain::models::{IssPass, ISS_NORAD_ID};
+use crate::domain::orbit::{parse_tle_text, OrbitState, Propagator};
+use crate::domain::passes::{find_passes, Observer};
+use crate::repo::tle_repo::TleRepo;
+
+/// Service for ingesting TLE data and propagating the ISS orbit.
+#[derive(Clone)]
+pub struct OrbitService {
+    repo: TleRepo,
+    client: reqwest::Client,
+    tle_url: String,
+    tle_file: String,
+    propagator: Arc<RwLock<Option<Propagator>>>,
+}
+
+impl OrbitService {
+    pub fn new(repo: TleRepo, tle_url: String, tle_file: String) -> Self {
+        Self {
+            repo,
+            client: reqwest::Client::new(),
+            tle_url,
+            tle_file,
+            propagator: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Fetches the current ISS TLE (falling back to the local file) and stores it.
+    /// Returns `true` if a new element set was stored.
+    pub async fn fetch_and_store_tle(&self) -> Result<bool> {
+        let (text, source) = match self.fetch_remote_tle().await {
+            Ok(text) => (text, self.tle_url.clone()),
+            Err(e) => {
+                warn!("TLE download failed, falling back to local file: {:?}", e);
+                if self.tle_file.is_empty() {
+                    anyhow::bail!("TLE download failed and no TLE_FILE_PATH is configured");
+                }
+                let text = tokio::fs::read_to_string(&self.tle_file)
+                    .await
+                    .with_context(|| format!("Failed to read TLE file {}", self.tle_file))?;
+                (text, format!("file://{}", self.tle_file))
+            }
+        };
+
+        let (name, line1, line2) = parse_tle_text(&text).context("No TLE found in response")?;
+        let propagator = Propagator::from_tle(name.as_deref(), &line1, &line2)?;
+        let inserted = self
+            .repo
+            .insert(
+                propagator.norad_id() as i64,
+                name.as_deref(),
+                &line1,
+                &line2,
+                propagator.epoch(),
+                &source,
+            )
+            .await?;
+
+        if inserted {
+            info!("Stored new TLE for NORAD {} (epoch {})", propagator.norad_id(), propagator.epoch());
+        }
+        // Never replace a fresher element set with an older one (e.g. from the fallback file).
+        let mut current = self.propagator.write().unwrap();
+        if current.as_ref().is_none_or(|p| p.epoch() <= propagator.epoch()) {
+            *current = Some(propagator);
+        }
+        Ok(inserted)
+    }
+
+    /// Predicts the ISS position at `at`. Returns `None` if no TLE has been ingested yet.
+    pub async fn predict(&self, at: DateTime<Utc>) -> Result<Option<(OrbitState, DateTime<Utc>)>> {
+        let Some(propagator) = self.current_propagator().await? else {
+            return Ok(None);
+        };
+        let state = propagator.propagate(at)?;
+        Ok(Some((state, propagator.epoch())))
+    }
+
+    /// Predicts ISS passes over an observer for the next `days` days.
+    /// Returns `None` if no TLE has been ingested yet.
+    pub async fn predict_passes(
+        &self,
+        observer: Observer,
+        days: i64,
+        min_elevation: f64,
+    ) -> Result<Option<Vec<IssPass>>> {
+        let Some(propagator) = self.current_propagator().await? else {
+            return Ok(None);
+        };
+        let from = Utc::now();
+        let to = from + Duration::days(days);
+        // Tens of thousands of propagations: keep them off the async executor.
+        let passes = tokio::task::spawn_blocking(move || {
+            find_passes(&propagator, observer, from, to, min_elevation)
+        })
+        .await??;
+        Ok(Some(passes))
+    }
+
+    // --- Private Helper Functions ---
+
+    async fn fetch_remote_tle(&self) -> Result<String> {
+        if self.tle_url.is_empty() {
+            anyhow::bail!("TLE_API_URL is not configured");
+        }
+        let resp = self.client.get(&self.tle_url)
+            .timeout(std::time::Duration::from_secs(20))
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            anyhow::bail!("TLE request failed with status {}", resp.status());
+        }
+        Ok(resp.text().await?)
+    }
+
+    /// Returns the cached propagator, loading the newest stored TLE on first use.
+    async fn current_propagator(&self) -> Result<Option<Propagator>> {
+        if let Some(p) = self.propagator.read().unwrap().clone() {
+            return Ok(Some(p));
+        }
+        let Some(tle) = self.repo.get_latest(ISS_NORAD_ID).await? else {
+            return Ok(None);
+        };
+        let propagator = Propagator::from_tle(tle.name.as_deref(), &tle.line1, &tle.line2)?;
+        *self.propagator.write().unwrap() = Some(propagator.clone());
+        Ok(Some(propagator))
+    }
+}
@@ -1,30 +1,304 @@
-use anyhow::Result;
-use serde_json::Value;
-use tracing::error;
+use std::collections::{HashMap, HashSet};
 
-use crate::domain::utils::{s_pick, t_pick};
-use crate::domain::validation::OsdrItemValidation;
-use crate::repo::osdr_repo::OsdrRepo;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use tracing::{error, info, warn};
+
+use crate::domain::models::{
+    NewOsdrItem, NewRejectedOsdrItem, OsdrRejectedItem, OsdrRevalidation, OsdrSyncRun, OsdrSyncSummary,
+};
+use crate::domain::osdr::normalize;
+use crate::domain::osdr_mapping::OsdrMapping;
+use crate::domain::validation::{OsdrItemValidation, ValidationIssue};
+use crate::repo::{cache_repo::CacheRepo, osdr_repo::OsdrRepo};
+
+/// Upper bound on upstream pages followed in one sync, in case `next` links loop.
+const MAX_PAGES: usize = 200;
+/// Redis key holding the latest `/osdr/stats` result.
+const STATS_CACHE_KEY: &str = "osdr:stats";
 
 /// Service for handling OSDR data fetching and processing.
 #[derive(Clone)]
 pub struct OsdrService {
     repo: OsdrRepo,
+    cache: CacheRepo,
     client: reqwest::Client,
     nasa_url: String,
+    withdraw_after: i32,
+    mapping_path: String,
 }
 
 impl OsdrService {
-    pub fn new(repo: OsdrRepo, nasa_url: String) -> Self {
+    /// Datasets missing from `withdraw_after` consecutive complete syncs are marked withdrawn.
+    /// Field-mapping rules are read from `mapping_path` on every sync, so they can change
+    /// without a restart.
+    pub fn new(
+        repo: OsdrRepo,
+        cache: CacheRepo,
+        nasa_url: String,
+        withdraw_after: i32,
+        mapping_path: String,
+    ) -> Self {
         Self {
             repo,
+            cache,
             client: reqwest::Client::new(),
             nasa_url,
+            withdraw_after: withdraw_after.max(1),
+            mapping_path,
+        }
+    }
+
+    /// Logs which field-mapping rules syncs will use, so a missing or broken file shows up
+    /// at startup rather than at the first sync.
+    pub async fn log_mapping_source(&self) {
+        let exists = tokio::fs::try_exists(&self.mapping_path).await.unwrap_or(false);
+        match self.load_mapping().await {
+            Err(e) => warn!("OSDR mapping rules are unusable, syncs will fail: {:#}", e),
+            Ok(_) if self.mapping_path.is_empty() => info!("OSDR mapping: built-in rules (OSDR_MAPPING_PATH is empty)"),
+            Ok(_) if !exists => info!("OSDR mapping: built-in rules ({} not found)", self.mapping_path),
+            Ok(_) => info!("OSDR mapping: rules from {}", self.mapping_path),
+        }
+    }
+
+    /// Records a new sync run in `osdr_sync_runs` and runs it to completion.
+    /// Returns `None` without syncing while another run is in progress.
+    pub async fn fetch_and_store_osdr(&self, full: bool) -> Result<Option<OsdrSyncSummary>> {
+        let (run_id, started) = self.start_sync(full).await?;
+        if !started {
+            info!("OSDR sync skipped: run {} is still in progress", run_id);
+            return Ok(None);
+        }
+        self.run_sync(run_id, full).await.map(Some)
+    }
+
+    /// Records a new sync run without starting it, so callers can hand out its ID first.
+    /// If a run is already in progress, returns its ID with `false` instead.
+    pub async fn start_sync(&self, full: bool) -> Result<(i64, bool)> {
+        self.repo.start_run(full).await
+    }
+
+    /// Runs the sync recorded as `run_id` and stores its counters or error.
+    pub async fn run_sync(&self, run_id: i64, full: bool) -> Result<OsdrSyncSummary> {
+        let mut summary = OsdrSyncSummary::default();
+        let result = self.sync(run_id, full, &mut summary).await;
+        let error = result.as_ref().err().map(|e| format!("{:#}", e));
+        if let Err(e) = self.repo.finish_run(run_id, &summary, error.as_deref()).await {
+            error!("Failed to record OSDR sync run {}: {:?}", run_id, e);
+        }
+        if result.is_ok() {
+            if let Err(e) = self.refresh_stats().await {
+                error!("Failed to refresh OSDR stats after sync run {}: {:?}", run_id, e);
+            }
+        }
+        result.map(|_| summary)
+    }
+
+    /// Faceted dataset statistics, served from the cache when present.
+    pub async fn stats(&self) -> Result<Value> {
+        match self.cache.get_latest(STATS_CACHE_KEY) {
+            Ok(stats) => Ok(stats),
+            Err(_) => self.refresh_stats().await,
+        }
+    }
+
+    /// Recomputes the statistics from `osdr_items` and stores them in the cache.
+    pub async fn refresh_stats(&self) -> Result<Value> {
+        let mut stats = self.repo.status_stats().await?;
+        let mut organisms = HashMap::new();
+        let mut missions = HashMap::new();
+        let mut assay_types = HashMap::new();
+        for raw in self.repo.list_active_raw().await? {
+            let d = normalize(&raw);
+            d.organism.into_iter().for_each(|v| *organisms.entry(v).or_insert(0) += 1);
+            d.mission.into_iter().for_each(|v| *missions.entry(v).or_insert(0) += 1);
+            d.assay_types.into_iter().for_each(|v| *assay_types.entry(v).or_insert(0) += 1);
+        }
+        stats["by_organism"] = facet(organisms);
+        stats["by_mission"] = facet(missions);
+        stats["by_assay_type"] = facet(assay_types);
+        stats["generated_at"] = json!(Utc::now());
+
+        if let Err(e) = self.cache.save(STATS_CACHE_KEY, &stats) {
+            error!("Failed to cache OSDR stats: {:?}", e);
+        }
+        Ok(stats)
+    }
+
+    /// Status and counters of a recorded sync run.
+    pub async fn get_run(&self, run_id: i64) -> Result<Option<OsdrSyncRun>> {
+        self.repo.get_run(run_id).await
+    }
+
+    /// Shows how the current mapping rules treat a sample upstream page or item,
+    /// without writing anything.
+    pub async fn dry_run(&self, sample: &Value) -> Result<Value> {
+        let mapping = self.load_mapping().await?;
+        let items: Vec<Value> = page_items(sample)
+            .iter()
+            .map(|item| {
+                let errors = screen(&mapping, item.clone()).err().map(|r| r.errors);
+                json!({
+                    "outcome": if errors.is_some() { "rejected" } else { "accepted" },
+                    "errors": errors,
+                    "fields": mapping.explain(item),
+                })
+            })
+            .collect();
+        Ok(json!({
+            "mapping": mapping,
+            "next_page": next_page_url(sample, &self.nasa_url),
+            "items": items,
+        }))
+    }
+
+    /// Lists quarantined items page by page, with the total count.
+    pub async fn rejected(&self, limit: i64, offset: i64) -> Result<(Vec<OsdrRejectedItem>, i64)> {
+        self.repo.list_rejected(limit, offset).await
+    }
+
+    /// Re-checks every quarantined item against the current validation and mapping rules.
+    /// Items that now pass are stored like synced ones and leave the quarantine; when several
+    /// payloads of one dataset pass, the most recently rejected one is stored.
+    pub async fn revalidate_rejected(&self) -> Result<OsdrRevalidation> {
+        let mapping = self.load_mapping().await?;
+        let rejected = self.repo.all_rejected().await?;
+        let mut result = OsdrRevalidation { checked: rejected.len(), ..Default::default() };
+        // Keyed by dataset ID, as in `sync`, so each dataset is written once.
+        let mut accepted: HashMap<String, (DateTime<Utc>, NewOsdrItem)> = HashMap::new();
+        let mut released = Vec::new();
+        let mut still_rejected = Vec::new();
+
+        for r in rejected {
+            match screen(&mapping, r.raw) {
+                Ok(item) => {
+                    released.push(r.id);
+                    let newer = accepted
+                        .get(&item.dataset_id)
+                        .is_none_or(|(at, _)| r.last_rejected_at > *at);
+                    if newer {
+                        accepted.insert(item.dataset_id.clone(), (r.last_rejected_at, item));
+                    }
+                }
+                Err(still) => still_rejected.push((r.id, json!(still.errors))),
+            }
+        }
+
+        let items: Vec<NewOsdrItem> = accepted.into_values().map(|(_, item)| item).collect();
+        self.repo.resolve_rejected(&items, &released, &still_rejected).await?;
+        result.accepted = released.len();
+        result.still_rejected = still_rejected.len();
+
+        info!(
+            "Re-validated {} quarantined OSDR items: {} accepted, {} still rejected",
+            result.checked, result.accepted, result.still_rejected
+        );
+        if result.accepted > 0 {
+            if let Err(e) = self.refresh_stats().await {
+                error!("Failed to refresh OSDR stats after re-validation: {:?}", e);
+            }
+        }
+        Ok(result)
+    }
+
+    // --- Private Helper Functions ---
+
+    /// Reads the field-mapping rules, falling back to the built-in ones if the file is absent.
+    async fn load_mapping(&self) -> Result<OsdrMapping> {
+        if self.mapping_path.is_empty() {
+            return Ok(OsdrMapping::default());
         }
+        let text = match tokio::fs::read_to_string(&self.mapping_path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(OsdrMapping::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read OSDR mapping {}", self.mapping_path)),
+        };
+        serde_json::from_str(&text).with_context(|| format!("Invalid OSDR mapping {}", self.mapping_path))
     }
 
-    pub async fn fetch_and_store_osdr(&self) -> Result<usize> {
-        let resp = self.client.get(&self.nasa_url)
+    /// Syncs OSDR datasets, following upstream pagination. Items updated at or before the
+    /// stored watermark are skipped unless `full` is set, unchanged content is never rewritten,
+    /// and all writes plus the new watermark are committed in one transaction.
+    /// Counters are kept in `summary` as they go, so a failed run still reports its progress.
+    /// Items failing validation are quarantined under `run_id`.
+    async fn sync(&self, run_id: i64, full: bool, summary: &mut OsdrSyncSummary) -> Result<()> {
+        let mapping = self.load_mapping().await?;
+        let watermark = if full { None } else { self.repo.get_watermark(&self.nasa_url).await? };
+        // Keyed by dataset ID so an item repeated across pages is written once.
+        let mut items: HashMap<String, NewOsdrItem> = HashMap::new();
+        // Every dataset ID listed upstream, and those that passed validation.
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut valid: Vec<String> = Vec::new();
+        let mut rejected: Vec<NewRejectedOsdrItem> = Vec::new();
+        let mut complete = true;
+        let mut next = Some(self.nasa_url.clone());
+
+        while let Some(url) = next.take() {
+            if summary.pages >= MAX_PAGES {
+                warn!("OSDR sync stopped after {} pages", MAX_PAGES);
+                complete = false;
+                break;
+            }
+            let json = self.fetch_page(&url).await?;
+            summary.pages += 1;
+            next = next_page_url(&json, &url);
+
+            for item in page_items(&json) {
+                summary.fetched += 1;
+                let item = match screen(&mapping, item) {
+                    Ok(item) => item,
+                    Err(r) => {
+                        warn!(
+                            "Rejected OSDR item {}: {}",
+                            r.dataset_id.as_deref().unwrap_or("without dataset ID"),
+                            r.errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+                        );
+                        summary.invalid += 1;
+                        seen.extend(r.dataset_id.clone());
+                        rejected.push(r);
+                        continue;
+                    }
+                };
+
+                seen.insert(item.dataset_id.clone());
+                valid.push(item.dataset_id.clone());
+                if let (Some(u), Some(w)) = (item.updated_at, watermark) {
+                    if u <= w {
+                        summary.skipped += 1;
+                        continue;
+                    }
+                }
+                items.insert(item.dataset_id.clone(), item);
+            }
+        }
+
+        let items: Vec<NewOsdrItem> = items.into_values().collect();
+        let new_watermark = items.iter().filter_map(|i| i.updated_at).max().max(watermark);
+        // Only a listing that was walked to the end tells which datasets are gone.
+        let seen: Vec<String> = seen.into_iter().collect();
+        let (written, withdrawn) = self.repo
+            .store_sync(&self.nasa_url, &items, new_watermark, complete.then_some(seen.as_slice()), self.withdraw_after)
+            .await?;
+        summary.written = written;
+        summary.withdrawn = withdrawn;
+        summary.unchanged = items.len() - summary.written;
+        summary.watermark = new_watermark;
+
+        // Datasets that now arrive valid leave the quarantine before this run's rejects go in.
+        self.repo.release_rejected(&valid).await?;
+        self.repo.quarantine(&self.nasa_url, Some(run_id), &rejected).await?;
+
+        info!(
+            "OSDR sync: {} pages, {} items, {} written, {} unchanged, {} skipped by watermark, {} invalid, {} withdrawn",
+            summary.pages, summary.fetched, summary.written, summary.unchanged, summary.skipped, summary.invalid,
+            summary.withdrawn
+        );
+        Ok(())
+    }
+
+    async fn fetch_page(&self, url: &str) -> Result<Value> {
+        let resp = self.client.get(url)
             .timeout(std::time::Duration::from_secs(45))
             .send()
             .await?;
@@ -32,36 +306,87 @@ impl OsdrService {
         if !resp.status().is_success() {
             anyhow::bail!("OSDR request failed with status {}", resp.status());
         }
+        Ok(resp.json().await?)
+    }
+}
 
-        let json: Value = resp.json().await?;
-        let items = if let Some(a) = json.as_array() {
-            a.clone()
-        } else if let Some(v) = json.get("items").and_then(|x| x.as_array()) {
-            v.clone()
-        } else if let Some(v) = json.get("results").and_then(|x| x.as_array()) {
-            v.clone()
-        } else {
-            vec![json]
-        };
+/// Extracts the items of one page: a bare array, or an `items`/`results` array.
+fn page_items(json: &Value) -> Vec<Value> {
+    if let Some(a) = json.as_array() {
+        a.clone()
+    } else if let Some(v) = json.get("items").and_then(|x| x.as_array()) {
+        v.clone()
+    } else if let Some(v) = json.get("results").and_then(|x| x.as_array()) {
+        v.clone()
+    } else {
+        vec![json.clone()]
+    }
+}
 
-        let mut written_count = 0;
-        for item in items {
-            // Validate the item before processing
-            if let Err(e) = serde_json::from_value::<OsdrItemValidation>(item.clone()) {
-                error!("Failed to deserialize or validate OSDR item: {:?}. Item: {:?}", e, item);
-                continue;
-            }
+/// Finds the next page link (`next`, `links.next` or `_links.next.href`), resolved against `current`.
+fn next_page_url(json: &Value, current: &str) -> Option<String> {
+    let next = json.get("next").and_then(Value::as_str)
+        .or_else(|| json.pointer("/links/next").and_then(Value::as_str))
+        .or_else(|| json.pointer("/_links/next/href").and_then(Value::as_str))
+        .filter(|s| !s.is_empty())?;
+    let resolved = reqwest::Url::parse(current).ok()?.join(next).ok()?.to_string();
+    (resolved != current).then_some(resolved)
+}
 
-            let dataset_id = s_pick(&item, &["dataset_id", "id", "uuid", "studyId", "accession", "osdr_id"]);
-            let title = s_pick(&item, &["title", "name", "label"]);
-            let status = s_pick(&item, &["status", "state", "lifecycle"]);
-            let updated_at = t_pick(&item, &["updated", "updated_at", "modified", "lastUpdated", "timestamp"]);
+/// `[{ value, count }]`, most frequent first.
+fn facet(counts: HashMap<String, i64>) -> Value {
+    let mut counts: Vec<(String, i64)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.into_iter().map(|(value, count)| json!({ "value": value, "count": count })).collect()
+}
 
-            if let Some(id) = dataset_id {
-                self.repo.upsert(&id, title.as_deref(), status.as_deref(), updated_at, &item).await?;
-                written_count += 1;
-            }
-        }
-        Ok(written_count)
+/// Validates an upstream item and applies the mapping rules, or explains why it is rejected.
+fn screen(mapping: &OsdrMapping, item: Value) -> Result<NewOsdrItem, NewRejectedOsdrItem> {
+    let mapped = mapping.map(&item);
+    let mut errors = OsdrItemValidation::check(&item).err().unwrap_or_default();
+    if mapped.dataset_id.is_none() {
+        errors.push(ValidationIssue::new("", "no dataset ID found by the mapping rules"));
+    }
+    match mapped.dataset_id {
+        Some(dataset_id) if errors.is_empty() => Ok(NewOsdrItem {
+            dataset_id,
+            title: mapped.title,
+            status: mapped.status,
+            updated_at: mapped.updated_at,
+            raw: item,
+        }),
+        dataset_id => Err(NewRejectedOsdrItem { dataset_id, raw: item, errors }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_accepts_mapped_item() {
+        let item = json!({ "id": "OSD-1", "name": "Mice", "updated": "2024-05-01T00:00:00Z" });
+        let accepted = screen(&OsdrMapping::default(), item.clone()).unwrap();
+        assert_eq!(accepted.dataset_id, "OSD-1");
+        assert_eq!(accepted.title.as_deref(), Some("Mice"));
+        assert!(accepted.updated_at.is_some());
+        assert_eq!(accepted.raw, item);
+    }
+
+    #[test]
+    fn screen_rejects_item_without_dataset_id() {
+        let rejected = screen(&OsdrMapping::default(), json!({ "title": "Mice" })).unwrap_err();
+        assert_eq!(rejected.dataset_id, None);
+        assert_eq!(rejected.errors.len(), 1);
+        assert_eq!(rejected.errors[0].path, "");
+        assert_eq!(rejected.errors[0].message, "no dataset ID found by the mapping rules");
+    }
+
+    #[test]
+    fn screen_rejects_invalid_item_keeping_its_dataset_id() {
+        let rejected = screen(&OsdrMapping::default(), json!({ "dataset_id": "OSD-1", "title": "" })).unwrap_err();
+        assert_eq!(rejected.dataset_id.as_deref(), Some("OSD-1"));
+        assert_eq!(rejected.errors.len(), 1);
+        assert_eq!(rejected.errors[0].path, "/title");
     }
 }
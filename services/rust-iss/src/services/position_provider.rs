@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::domain::models::ISS_NORAD_ID;
+use crate::domain::orbit::WGS84_A;
+use crate::domain::solar::is_sunlit;
+use crate::services::orbit_service::OrbitService;
+
+/// A position reading from one provider, ready to be stored in `iss_fetch_log`.
+#[derive(Debug, Clone)]
+pub struct ProviderFix {
+    /// `<provider>:<source>`, stored as the row's `source_url`.
+    pub source_url: String,
+    /// Payload in the wheretheiss.at shape (`latitude`, `longitude`, `altitude`, ...).
+    pub payload: Value,
+}
+
+/// A source of current satellite positions. Providers are tried in priority order.
+#[async_trait]
+pub trait IssPositionProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Fetches the current position of a satellite. Fails if the provider is unavailable
+    /// or cannot locate this satellite.
+    async fn fetch(&self, satellite_id: i64) -> Result<ProviderFix>;
+}
+
+/// api.wheretheiss.at, which serves every satellite by NORAD ID.
+pub struct WhereTheIssProvider {
+    client: reqwest::Client,
+    iss_url: String,
+    satellite_url_template: String,
+}
+
+impl WhereTheIssProvider {
+    /// `satellite_url_template` is used for every satellite other than the ISS,
+    /// with `{id}` replaced by the NORAD ID.
+    pub fn new(iss_url: String, satellite_url_template: String) -> Self {
+        Self { client: reqwest::Client::new(), iss_url, satellite_url_template }
+    }
+}
+
+#[async_trait]
+impl IssPositionProvider for WhereTheIssProvider {
+    fn name(&self) -> &'static str {
+        "wheretheiss"
+    }
+
+    async fn fetch(&self, satellite_id: i64) -> Result<ProviderFix> {
+        let url = if satellite_id == ISS_NORAD_ID {
+            self.iss_url.clone()
+        } else {
+            self.satellite_url_template.replace("{id}", &satellite_id.to_string())
+        };
+        let payload = get_json(&self.client, &url).await?;
+        if payload.get("latitude").is_none() || payload.get("longitude").is_none() {
+            anyhow::bail!("response has no coordinates");
+        }
+        Ok(ProviderFix { source_url: format!("{}:{}", self.name(), url), payload })
+    }
+}
+
+/// An Open Notify-style `iss-now` API. It only knows the ISS and reports no altitude or velocity.
+pub struct OpenNotifyProvider {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl OpenNotifyProvider {
+    pub fn new(url: String) -> Self {
+        Self { client: reqwest::Client::new(), url }
+    }
+}
+
+#[async_trait]
+impl IssPositionProvider for OpenNotifyProvider {
+    fn name(&self) -> &'static str {
+        "open-notify"
+    }
+
+    async fn fetch(&self, satellite_id: i64) -> Result<ProviderFix> {
+        if satellite_id != ISS_NORAD_ID {
+            anyhow::bail!("only the ISS is available");
+        }
+        let raw = get_json(&self.client, &self.url).await?;
+        let coord = |k: &str| {
+            let v = &raw["iss_position"][k];
+            v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse::<f64>().ok()))
+        };
+        let payload = json!({
+            "latitude": coord("latitude").context("response has no latitude")?,
+            "longitude": coord("longitude").context("response has no longitude")?,
+            "timestamp": raw["timestamp"],
+            "raw": raw,
+        });
+        Ok(ProviderFix { source_url: format!("{}:{}", self.name(), self.url), payload })
+    }
+}
+
+/// Propagates the latest stored ISS TLE. Works offline but only for the ISS.
+pub struct TleProvider {
+    orbit: OrbitService,
+}
+
+impl TleProvider {
+    pub fn new(orbit: OrbitService) -> Self {
+        Self { orbit }
+    }
+}
+
+#[async_trait]
+impl IssPositionProvider for TleProvider {
+    fn name(&self) -> &'static str {
+        "tle"
+    }
+
+    async fn fetch(&self, satellite_id: i64) -> Result<ProviderFix> {
+        if satellite_id != ISS_NORAD_ID {
+            anyhow::bail!("only the ISS TLE is available");
+        }
+        let now = Utc::now();
+        let (state, epoch) = self.orbit.predict(now).await?.context("no TLE data available yet")?;
+        let visibility = if is_sunlit(state.ecef_km, now) { "daylight" } else { "eclipsed" };
+        // Diameter of the area from which the satellite is above the horizon.
+        let footprint = 2.0 * WGS84_A * (WGS84_A / (WGS84_A + state.altitude_km)).acos();
+        let payload = json!({
+            "latitude": state.lat,
+            "longitude": state.lon,
+            "altitude": state.altitude_km,
+            "velocity": state.velocity_kmh,
+            "visibility": visibility,
+            "footprint": footprint,
+            "timestamp": now.timestamp(),
+            "tle_epoch": epoch,
+        });
+        Ok(ProviderFix {
+            source_url: format!("{}:{}@{}", self.name(), satellite_id, epoch.to_rfc3339()),
+            payload,
+        })
+    }
+}
+
+/// Builds providers from a comma-separated priority list such as `wheretheiss,open-notify,tle`.
+/// Unknown names are skipped; an empty result falls back to wheretheiss.at alone.
+pub fn build_providers(
+    names: &str,
+    iss_url: &str,
+    satellite_url_template: &str,
+    open_notify_url: &str,
+    orbit: &OrbitService,
+) -> Vec<Box<dyn IssPositionProvider>> {
+    let wheretheiss = || -> Box<dyn IssPositionProvider> {
+        Box::new(WhereTheIssProvider::new(iss_url.to_string(), satellite_url_template.to_string()))
+    };
+    let mut providers: Vec<Box<dyn IssPositionProvider>> = names
+        .split(',')
+        .filter_map(|name| match name.trim() {
+            "wheretheiss" => Some(wheretheiss()),
+            "open-notify" => Some(Box::new(OpenNotifyProvider::new(open_notify_url.to_string())) as _),
+            "tle" => Some(Box::new(TleProvider::new(orbit.clone())) as _),
+            other => {
+                warn!("Unknown ISS position provider '{}' ignored", other);
+                None
+            }
+        })
+        .collect();
+    if providers.is_empty() {
+        providers.push(wheretheiss());
+    }
+    providers
+}
+
+// --- Private Helper Functions ---
+
+async fn get_json(client: &reqwest::Client, url: &str) -> Result<Value> {
+    let resp = client.get(url)
+        .timeout(std::time::Duration::from_secs(20))
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        anyhow::bail!("request failed with status {}", resp.status());
+    }
+    Ok(resp.json().await?)
+}
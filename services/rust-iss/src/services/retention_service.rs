@@ -0,0 +1,63 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::domain::models::RetentionRun;
+use crate::repo::db::ISS_PARTITION_MONTHS_AHEAD;
+use crate::repo::iss_repo::IssRepo;
+
+/// Number of past runs reported by `stats`.
+const STATS_RECENT_RUNS: i64 = 10;
+
+/// Service maintaining `iss_fetch_log` storage: raw rows are kept for `retention_days`,
+/// older ones are downsampled to one point per `rollup_minutes` and deleted, and monthly
+/// partitions are created ahead of time.
+#[derive(Clone)]
+pub struct RetentionService {
+    repo: IssRepo,
+    retention_days: i64,
+    rollup_minutes: i64,
+}
+
+impl RetentionService {
+    pub fn new(repo: IssRepo, retention_days: i64, rollup_minutes: i64) -> Self {
+        Self {
+            repo,
+            retention_days: retention_days.max(1),
+            rollup_minutes: rollup_minutes.max(1),
+        }
+    }
+
+    /// Rolls up and prunes everything older than the retention window.
+    pub async fn run(&self) -> Result<RetentionRun> {
+        let cutoff = Utc::now() - Duration::days(self.retention_days);
+        let run = self.repo.prune(cutoff, self.rollup_minutes).await?;
+        info!(
+            "Retention pruned {} ISS rows before {} ({} rollup points written)",
+            run.deleted, run.cutoff, run.rolled_up
+        );
+        Ok(run)
+    }
+
+    /// Makes sure upcoming monthly partitions exist before rows arrive for them.
+    pub async fn ensure_partitions(&self) -> Result<()> {
+        self.repo.ensure_partitions(ISS_PARTITION_MONTHS_AHEAD).await
+    }
+
+    /// Policy, totals and recent runs of the retention job.
+    pub async fn stats(&self) -> Result<Value> {
+        let (rolled_up, deleted, raw_rows, rollup_rows) = self.repo.retention_totals().await?;
+        let runs = self.repo.list_retention_runs(STATS_RECENT_RUNS).await?;
+        Ok(json!({
+            "retention_days": self.retention_days,
+            "rollup_minutes": self.rollup_minutes,
+            "raw_rows": raw_rows,
+            "rollup_rows": rollup_rows,
+            "total_rolled_up": rolled_up,
+            "total_deleted": deleted,
+            "last_run": runs.first(),
+            "recent_runs": runs,
+        }))
+    }
+}
@@ -1,13 +1,20 @@
+use chrono::{DateTime, Utc};
 use reqwest::header::{HeaderMap, USER_AGENT};
+use serde_json::Value;
 use tracing::{error, info};
 
-use crate::repo::cache_repo::CacheRepo;
+use crate::domain::models::SpaceCacheEntry;
+use crate::repo::{cache_repo::CacheRepo, space_repo::SpaceRepo};
 
-/// A service dedicated to fetching data from various space-related APIs
-/// and caching the results in Redis.
+/// Cache keys written by the fetch jobs, one per upstream source.
+pub const SPACE_SOURCES: [&str; 5] = ["apod", "neo", "flr", "cme", "spacex"];
+
+/// A service dedicated to fetching data from various space-related APIs,
+/// caching the latest results in Redis and keeping every fetch in `space_cache`.
 #[derive(Clone)]
 pub struct SpaceService {
     cache: CacheRepo,
+    repo: SpaceRepo,
     client: reqwest::Client,
     nasa_key: String,
     apod_url: String,
@@ -21,6 +28,7 @@ impl SpaceService {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         cache: CacheRepo,
+        repo: SpaceRepo,
         nasa_key: String,
         apod_url: String,
         neo_url: String,
@@ -33,6 +41,7 @@ impl SpaceService {
 
         Self {
             cache,
+            repo,
             client: reqwest::Client::builder()
                 .default_headers(headers)
                 .build()
@@ -101,11 +110,59 @@ impl SpaceService {
             }
         };
 
+        // Redis serves `/latest`, so a history write failure must not keep it stale.
+        if let Err(e) = self.repo.append(source_key, &data).await {
+            error!("Failed to store {} fetch in space_cache: {:?}", source_key, e);
+        }
         self.cache.save(source_key, &data)?;
         info!("Successfully cached data for {}", source_key);
         Ok(())
     }
 
+    /// Latest payload of a source. On a Redis miss, e.g. after a flush, the newest stored
+    /// fetch is served and written back to Redis. A failed lookup counts as no data.
+    pub async fn latest(&self, source: &str) -> Option<Value> {
+        if let Ok(value) = self.cache.get_latest(source) {
+            return Some(value);
+        }
+        let entry = match self.repo.get_latest(source).await {
+            Ok(entry) => entry?,
+            Err(e) => {
+                error!("Failed to load the latest {} fetch from space_cache: {:?}", source, e);
+                return None;
+            }
+        };
+        if let Err(e) = self.cache.save(source, &entry.payload) {
+            error!("Failed to restore {} into the cache: {:?}", source, e);
+        }
+        Some(entry.payload)
+    }
+
+    /// Fills Redis keys that are missing from the newest stored fetch of each source.
+    pub async fn warm_cache(&self) -> anyhow::Result<()> {
+        for source in SPACE_SOURCES {
+            if self.cache.get_latest(source).is_ok() {
+                continue;
+            }
+            if let Some(entry) = self.repo.get_latest(source).await? {
+                self.cache.save(source, &entry.payload)?;
+                info!("Restored cached {} from the fetch of {}", source, entry.fetched_at);
+            }
+        }
+        Ok(())
+    }
+
+    /// Stored fetches of a source within `[from, to]`, newest first.
+    pub async fn history(
+        &self,
+        source: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SpaceCacheEntry>> {
+        self.repo.get_range(source, from, to, limit).await
+    }
+
     // --- Public methods for specific sources ---
 
     pub async fn fetch_apod(&self) -> anyhow::Result<()> {